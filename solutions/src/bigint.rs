@@ -1,12 +1,20 @@
 use std::ops;
 use std::cmp;
 use std::fmt;
+use std::hash;
+use std::error;
+use std::str::FromStr;
+use std::convert::TryFrom;
+use std::{slice, iter};
+use signed_bigint::SignedBigInt;
+use rng::XorShiftRng;
 
 pub trait Minimum {
     /// Return the smaller of the two
     fn min<'a>(&'a self, other: &'a Self) -> &'a Self;
 }
 
+
 /// Return a pointer to the minimal value of `v`.
 pub fn vec_min<T: Minimum>(v: &Vec<T>) -> Option<&T> {
     let mut min = None;
@@ -20,11 +28,136 @@ pub fn vec_min<T: Minimum>(v: &Vec<T>) -> Option<&T> {
 }
 
 pub struct BigInt {
-    data: Vec<u64>, // least significant digits first. The last block will *not* be 0.
+    data: LimbVec, // least significant digits first. The last block will *not* be 0.
+}
+
+// The number of digits that a `BigInt` can hold without allocating memory on the heap.
+const INLINE_LIMBS: usize = 2;
+
+// The storage for the digits of a `BigInt`. Small numbers are stored inline, and only larger ones go to the heap.
+// Either way, this dereferences to the slice of digits, and supports the few `Vec` operations that `BigInt` needs.
+#[derive(Clone)]
+enum LimbVec {
+    Inline(usize, [u64; INLINE_LIMBS]),
+    Heap(Vec<u64>),
+}
+
+impl LimbVec {
+    fn new() -> Self {
+        LimbVec::Inline(0, [0; INLINE_LIMBS])
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        if capacity <= INLINE_LIMBS {
+            LimbVec::new()
+        } else {
+            LimbVec::Heap(Vec::with_capacity(capacity))
+        }
+    }
+
+    // Move the digits to the heap, making room for at least `capacity` digits.
+    fn spill(&mut self, capacity: usize) -> &mut Vec<u64> {
+        if let LimbVec::Inline(..) = *self {
+            let mut v = Vec::with_capacity(cmp::max(capacity, 2 * INLINE_LIMBS));
+            v.extend_from_slice(self);
+            *self = LimbVec::Heap(v);
+        }
+        match *self {
+            LimbVec::Heap(ref mut v) => v,
+            LimbVec::Inline(..) => unreachable!(),
+        }
+    }
+
+    fn push(&mut self, digit: u64) {
+        match *self {
+            LimbVec::Inline(ref mut len, ref mut digits) if *len < INLINE_LIMBS => {
+                digits[*len] = digit;
+                *len += 1;
+            }
+            _ => {
+                let len = self.len();
+                self.spill(len + 1).push(digit);
+            }
+        }
+    }
+
+    fn truncate(&mut self, new_len: usize) {
+        match *self {
+            LimbVec::Inline(ref mut len, _) => *len = cmp::min(*len, new_len),
+            LimbVec::Heap(ref mut v) => v.truncate(new_len),
+        }
+    }
+
+    // Change the number of digits to `new_len`, filling up with zeros if it grows.
+    fn resize(&mut self, new_len: usize) {
+        match *self {
+            LimbVec::Inline(ref mut len, ref mut digits) if new_len <= INLINE_LIMBS => {
+                for digit in digits.iter_mut().take(new_len).skip(*len) {
+                    *digit = 0;
+                }
+                *len = new_len;
+            }
+            _ => self.spill(new_len).resize(new_len, 0),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    // Remove the first `count` digits, moving the others down.
+    fn remove_front(&mut self, count: usize) {
+        let len = self.len();
+        self.copy_within(count.., 0);
+        self.truncate(len - count);
+    }
+
+    // Insert `count` zero digits at the front, moving the others up.
+    fn insert_zeros_front(&mut self, count: usize) {
+        let len = self.len();
+        self.resize(len + count);
+        self.copy_within(..len, count);
+        for digit in self[..count].iter_mut() {
+            *digit = 0;
+        }
+    }
+}
+
+impl ops::Deref for LimbVec {
+    type Target = [u64];
+    fn deref(&self) -> &[u64] {
+        match *self {
+            LimbVec::Inline(len, ref digits) => &digits[..len],
+            LimbVec::Heap(ref v) => v,
+        }
+    }
+}
+
+impl ops::DerefMut for LimbVec {
+    fn deref_mut(&mut self) -> &mut [u64] {
+        match *self {
+            LimbVec::Inline(len, ref mut digits) => &mut digits[..len],
+            LimbVec::Heap(ref mut v) => v,
+        }
+    }
+}
+
+impl From<Vec<u64>> for LimbVec {
+    fn from(v: Vec<u64>) -> Self {
+        if v.len() <= INLINE_LIMBS {
+            let mut result = LimbVec::new();
+            for digit in v {
+                result.push(digit);
+            }
+            result
+        } else {
+            LimbVec::Heap(v)
+        }
+    }
 }
 
 // Add with carry, returning the sum and the carry
-fn overflowing_add(a: u64, b: u64, carry: bool) -> (u64, bool) {
+pub(crate) fn overflowing_add(a: u64, b: u64, carry: bool) -> (u64, bool) {
     let sum = u64::wrapping_add(a, b);
     let carry_n = if carry { 1 } else { 0 };
     if sum >= a { // the first sum did not overflow
@@ -38,7 +171,7 @@ fn overflowing_add(a: u64, b: u64, carry: bool) -> (u64, bool) {
 }
 
 // Subtract with carry, returning the difference and the carry
-fn overflowing_sub(a: u64, b: u64, carry: bool) -> (u64, bool) {
+pub(crate) fn overflowing_sub(a: u64, b: u64, carry: bool) -> (u64, bool) {
     let diff = u64::wrapping_sub(a, b);
     let carry_n = if carry { 1 } else { 0 };
     if diff <= a { // the first diff did not wrap
@@ -51,13 +184,387 @@ fn overflowing_sub(a: u64, b: u64, carry: bool) -> (u64, bool) {
     }
 }
 
+// Multiply-accumulate, computing `acc + a*b + carry` and returning the low digit and the high digit (the new carry).
+// This cannot overflow: (2^64-1) + (2^64-1)^2 + (2^64-1) = 2^128 - 1.
+pub(crate) fn mul_add(acc: u64, a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let wide = (acc as u128) + (a as u128) * (b as u128) + (carry as u128);
+    (wide as u64, (wide >> 64) as u64)
+}
+
+// Strip the most significant zero digits from a slice of digits.
+fn trim_limbs(mut v: &[u64]) -> &[u64] {
+    while let Some((&0, rest)) = v.split_last() {
+        v = rest;
+    }
+    v
+}
+
+// Add `src` into `dst`, propagating the carry, and return the carry out of the last digit of `dst`.
+fn add_limbs_carry(dst: &mut [u64], src: &[u64]) -> bool {
+    let src = trim_limbs(src);
+    let mut carry = false;
+    for (i, &digit) in src.iter().enumerate() {
+        let (sum, new_carry) = overflowing_add(dst[i], digit, carry);
+        dst[i] = sum;
+        carry = new_carry;
+    }
+    let mut idx = src.len();
+    while carry && idx < dst.len() {
+        let (sum, new_carry) = overflowing_add(dst[idx], 0, true);
+        dst[idx] = sum;
+        carry = new_carry;
+        idx += 1;
+    }
+    carry
+}
+
+// Add `src` into `dst`, propagating the carry. The caller has to make sure that the result fits into `dst`.
+fn add_limbs_into(dst: &mut [u64], src: &[u64]) {
+    let carry = add_limbs_carry(dst, src);
+    debug_assert!(!carry);
+}
+
+// Subtract `src` from `dst`, propagating the borrow. The caller has to make sure that `src <= dst`.
+fn sub_limbs_into(dst: &mut [u64], src: &[u64]) {
+    let src = trim_limbs(src);
+    let mut carry = false;
+    for (i, &digit) in src.iter().enumerate() {
+        let (diff, new_carry) = overflowing_sub(dst[i], digit, carry);
+        dst[i] = diff;
+        carry = new_carry;
+    }
+    let mut idx = src.len();
+    while carry {
+        let (diff, new_carry) = overflowing_sub(dst[idx], 0, true);
+        dst[idx] = diff;
+        carry = new_carry;
+        idx += 1;
+    }
+}
+
+// Sum of two slices of digits, as a new vector (that may have a trailing zero).
+fn add_limbs(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut result = Vec::with_capacity(long.len() + 1);
+    result.extend_from_slice(long);
+    result.push(0);
+    add_limbs_into(&mut result, short);
+    result
+}
+
+// Below this number of digits (of the shorter operand), we use schoolbook multiplication.
+const KARATSUBA_THRESHOLD: usize = 32;
+
+// Add `a*b` into `dst` by multiplying every digit of `a` with every digit of `b`.
+fn mul_schoolbook_into(dst: &mut [u64], a: &[u64], b: &[u64]) {
+    for (i, &a_digit) in a.iter().enumerate() {
+        if a_digit == 0 {
+            continue;
+        }
+        let mut carry = 0;
+        for (j, &b_digit) in b.iter().enumerate() {
+            let (digit, new_carry) = mul_add(dst[i + j], a_digit, b_digit, carry);
+            dst[i + j] = digit;
+            carry = new_carry;
+        }
+        add_limbs_into(&mut dst[i + b.len()..], &[carry]);
+    }
+}
+
+// Add `a*b` into `dst`, which has to have room for at least `a.len() + b.len()` digits.
+fn mul_limbs_into(dst: &mut [u64], a: &[u64], b: &[u64]) {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    if b.len() < KARATSUBA_THRESHOLD {
+        mul_schoolbook_into(dst, a, b);
+        return;
+    }
+    if a.len() >= 2 * b.len() {
+        // The operands are very unbalanced, so we cut `a` into pieces of the size of `b`.
+        for (i, chunk) in a.chunks(b.len()).enumerate() {
+            mul_limbs_into(&mut dst[i * b.len()..], chunk, b);
+        }
+        return;
+    }
+    // Karatsuba: With `a = a1*B + a0` and `b = b1*B + b0`, we have
+    // `a*b = a1*b1*B^2 + ((a0+a1)*(b0+b1) - a0*b0 - a1*b1)*B + a0*b0`.
+    // Since `a.len() < 2*b.len()`, both `a1` and `b1` are non-empty.
+    let half = a.len() / 2;
+    let (a0, a1) = a.split_at(half);
+    let (b0, b1) = b.split_at(half);
+    let low = mul_limbs(a0, b0);
+    let high = mul_limbs(a1, b1);
+    let mut mid = mul_limbs(&add_limbs(a0, a1), &add_limbs(b0, b1));
+    sub_limbs_into(&mut mid, &low);
+    sub_limbs_into(&mut mid, &high);
+    add_limbs_into(dst, &low);
+    add_limbs_into(&mut dst[half..], &mid);
+    add_limbs_into(&mut dst[2 * half..], &high);
+}
+
+// Product of two slices of digits, as a new vector (that may have trailing zeros).
+fn mul_limbs(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut result = vec![0; a.len() + b.len()];
+    mul_limbs_into(&mut result, a, b);
+    result
+}
+
+// Compare two slices of digits without trailing zeros, most significant digit first.
+fn cmp_limbs(a: &[u64], b: &[u64]) -> cmp::Ordering {
+    debug_assert!(trim_limbs(a).len() == a.len() && trim_limbs(b).len() == b.len());
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len());
+    }
+    for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+        if x != y {
+            return x.cmp(y);
+        }
+    }
+    cmp::Ordering::Equal
+}
+
+// Shift the digits to the left by `bits < 64`, returning a vector that is one digit longer.
+fn shl_limbs(a: &[u64], bits: u32) -> Vec<u64> {
+    debug_assert!(bits < 64);
+    let mut result = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0;
+    for &digit in a {
+        if bits == 0 {
+            result.push(digit);
+        } else {
+            result.push((digit << bits) | carry);
+            carry = digit >> (64 - bits);
+        }
+    }
+    result.push(carry);
+    result
+}
+
+// Shift the digits to the right by `bits < 64`, in place.
+pub(crate) fn shr_limbs_in_place(a: &mut [u64], bits: u32) {
+    debug_assert!(bits < 64);
+    if bits == 0 {
+        return;
+    }
+    for i in 0..a.len() {
+        let high = if i + 1 < a.len() { a[i + 1] << (64 - bits) } else { 0 };
+        a[i] = (a[i] >> bits) | high;
+    }
+}
+
+// Divide the digits in place by a single non-zero digit, returning the remainder. The quotient may end up
+// with trailing zeros.
+fn div_rem_digit_in_place(a: &mut [u64], divisor: u64) -> u64 {
+    debug_assert!(divisor != 0);
+    let mut rem: u64 = 0;
+    for digit in a.iter_mut().rev() {
+        // `rem < divisor`, so the quotient of this step fits into one digit.
+        let cur = ((rem as u128) << 64) | (*digit as u128);
+        *digit = (cur / divisor as u128) as u64;
+        rem = (cur % divisor as u128) as u64;
+    }
+    rem
+}
+
+// The remainder of dividing the digits by a single non-zero digit.
+fn rem_digit(a: &[u64], divisor: u64) -> u64 {
+    debug_assert!(divisor != 0);
+    a.iter().rev().fold(0, |rem, &digit| ((((rem as u128) << 64) | (digit as u128)) % divisor as u128) as u64)
+}
+
+// Divide the digits by a single non-zero digit, returning the quotient (which may have trailing zeros) and the remainder.
+fn div_rem_digit(a: &[u64], divisor: u64) -> (Vec<u64>, u64) {
+    let mut quotient = a.to_vec();
+    let rem = div_rem_digit_in_place(&mut quotient, divisor);
+    (quotient, rem)
+}
+
+// Long division of `a` by `b` (Knuth, TAOCP Vol. 2, Algorithm 4.3.1 D), returning quotient and remainder
+// (which may both have trailing zeros). Requires `b` to have at least two digits, and no trailing zeros.
+fn div_rem_limbs(a: &[u64], b: &[u64]) -> (Vec<u64>, Vec<u64>) {
+    let n = b.len();
+    debug_assert!(n >= 2 && b[n - 1] != 0);
+    if a.len() < n {
+        return (vec![], a.to_vec());
+    }
+    // Normalize, such that the most significant bit of the divisor is set. This makes the estimates for the
+    // quotient digits below be off by at most 2.
+    let shift = b[n - 1].leading_zeros();
+    let b = shl_limbs(b, shift);
+    let (b_top, b_next) = (b[n - 1] as u128, b[n - 2] as u128);
+    let mut a = shl_limbs(a, shift);
+    let mut quotient = vec![0; a.len() - n];
+    for j in (0..quotient.len()).rev() {
+        // Estimate the next quotient digit from the top two digits of the current remainder, and fix it up using
+        // the third one.
+        let top = ((a[j + n] as u128) << 64) | (a[j + n - 1] as u128);
+        let mut qhat = top / b_top;
+        let mut rhat = top % b_top;
+        while qhat >> 64 != 0 || qhat * b_next > ((rhat << 64) | (a[j + n - 2] as u128)) {
+            qhat -= 1;
+            rhat += b_top;
+            if rhat >> 64 != 0 {
+                break;
+            }
+        }
+        // Subtract `qhat * b` from the current window of `a`.
+        let mut mul_carry = 0;
+        let mut borrow = false;
+        for i in 0..n {
+            let (prod, new_mul_carry) = mul_add(0, qhat as u64, b[i], mul_carry);
+            let (diff, new_borrow) = overflowing_sub(a[i + j], prod, borrow);
+            a[i + j] = diff;
+            mul_carry = new_mul_carry;
+            borrow = new_borrow;
+        }
+        let (diff, new_borrow) = overflowing_sub(a[j + n], mul_carry, borrow);
+        a[j + n] = diff;
+        if new_borrow {
+            // This happens very rarely: `qhat` was still one too large, so we have to add `b` back.
+            qhat -= 1;
+            let mut carry = false;
+            for i in 0..n {
+                let (sum, new_carry) = overflowing_add(a[i + j], b[i], carry);
+                a[i + j] = sum;
+                carry = new_carry;
+            }
+            a[j + n] = a[j + n].wrapping_add(if carry { 1 } else { 0 });
+        }
+        quotient[j] = qhat as u64;
+    }
+    // What is left in `a` is the remainder, we just have to undo the normalization.
+    a.truncate(n);
+    shr_limbs_in_place(&mut a, shift);
+    (quotient, a)
+}
+
+// Arithmetic modulo an odd number `n` in Montgomery form: Instead of `a`, we store `a*R mod n` with `R = 2^(64*k)`
+// and `k` the number of digits of `n`. This makes reduction after a multiplication cheap, as dividing by `R`
+// amounts to dropping digits. All numbers are passed around as exactly `k` digits, possibly with trailing zeros.
+pub(crate) struct Montgomery {
+    modulus: Vec<u64>,
+    // `-modulus^(-1) mod 2^64`
+    neg_inv: u64,
+}
+
+impl Montgomery {
+    pub(crate) fn new(modulus: &[u64]) -> Self {
+        debug_assert!(!modulus.is_empty() && modulus[0] % 2 == 1);
+        // Newton iteration for the inverse modulo 2^64: Every step doubles the number of correct bits, and
+        // `modulus[0]` is its own inverse modulo 2^3.
+        let mut inv = modulus[0];
+        for _ in 0..5 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(modulus[0].wrapping_mul(inv)));
+        }
+        Montgomery { modulus: modulus.to_vec(), neg_inv: inv.wrapping_neg() }
+    }
+
+    // Pad (or trim) the digits to exactly the length of the modulus.
+    fn pad(&self, mut v: Vec<u64>) -> Vec<u64> {
+        debug_assert!(trim_limbs(&v).len() <= self.modulus.len());
+        v.resize(self.modulus.len(), 0);
+        v
+    }
+
+    // Convert `a < modulus` into Montgomery form.
+    fn to_mont(&self, a: &BigInt) -> Vec<u64> {
+        let mut shifted = vec![0; self.modulus.len()];
+        shifted.extend_from_slice(&a.data);
+        let (_, remainder) = if self.modulus.len() == 1 {
+            let (q, r) = div_rem_digit(&shifted, self.modulus[0]);
+            (q, vec![r])
+        } else {
+            div_rem_limbs(&shifted, &self.modulus)
+        };
+        self.pad(remainder)
+    }
+
+    // Convert back from Montgomery form.
+    fn from_mont(&self, a: &[u64]) -> BigInt {
+        let one = self.pad(vec![1]);
+        BigInt::from_vec(self.mul(a, &one))
+    }
+
+    // The number 1 in Montgomery form.
+    fn one(&self) -> Vec<u64> {
+        self.to_mont(&BigInt::new(1))
+    }
+
+    // Compute `a*b/R mod modulus`, interleaving the multiplication and the reduction digit by digit.
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = &self.modulus;
+        let k = n.len();
+        let mut t = self.mul_unreduced(a, b);
+        // Now `t < 2*n`, so at most one subtraction is left to do.
+        if cmp_limbs(trim_limbs(&t), n) != cmp::Ordering::Less {
+            sub_limbs_into(&mut t, n);
+        }
+        t.truncate(k);
+        t
+    }
+
+    // Like `mul`, but the final subtraction happens in constant time. `a` and `b` must be padded.
+    pub(crate) fn mul_ct(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = &self.modulus;
+        let k = n.len();
+        let t = self.mul_unreduced(a, b);
+        // Always compute `t - n`, and keep it only if that did not wrap around.
+        let mut diff = vec![0; k + 1];
+        let mut borrow = 0;
+        for j in 0..=k {
+            let (digit, new_borrow) = ct_sub_borrow(t[j], if j < k { n[j] } else { 0 }, borrow);
+            diff[j] = digit;
+            borrow = new_borrow;
+        }
+        let mut result = vec![0; k];
+        ct_select_limbs(&mut result, &t[..k], &diff[..k], borrow);
+        result
+    }
+
+    // Compute `a*b/R`, which is less than `2*modulus`, without the final reduction. The result has `k + 1`
+    // digits, where `k` is the length of the modulus. The running time only depends on `k` and the length of `a`.
+    fn mul_unreduced(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = &self.modulus;
+        let k = n.len();
+        let mut t = vec![0; k + 2];
+        for &a_digit in a {
+            ct_step();
+            // t += a_digit * b
+            let mut carry = 0;
+            for j in 0..k {
+                let (digit, new_carry) = mul_add(t[j], a_digit, b[j], carry);
+                t[j] = digit;
+                carry = new_carry;
+            }
+            let (sum, overflow) = t[k].overflowing_add(carry);
+            t[k] = sum;
+            t[k + 1] = overflow as u64;
+            // Add a multiple of `n` such that the lowest digit becomes 0, and shift that digit out.
+            let m = t[0].wrapping_mul(self.neg_inv);
+            let (_, mut carry) = mul_add(t[0], m, n[0], 0);
+            for j in 1..k {
+                let (digit, new_carry) = mul_add(t[j], m, n[j], carry);
+                t[j - 1] = digit;
+                carry = new_carry;
+            }
+            let (sum, overflow) = t[k].overflowing_add(carry);
+            t[k - 1] = sum;
+            t[k] = t[k + 1] + overflow as u64;
+            t[k + 1] = 0;
+        }
+        t.truncate(k + 1);
+        t
+    }
+}
+
 impl BigInt {
     /// Construct a BigInt from a "small" one.
     pub fn new(x: u64) -> Self {
         if x == 0 { // take care of our invariant!
-            BigInt { data: vec![] }
+            BigInt { data: LimbVec::new() }
         } else {
-            BigInt { data: vec![x] }
+            let mut data = LimbVec::new();
+            data.push(x);
+            BigInt { data }
         }
     }
 
@@ -75,7 +582,13 @@ impl BigInt {
         while v.len() > 0 && v[v.len()-1] == 0 {
             v.pop();
         }
-        BigInt { data: v }
+        BigInt { data: LimbVec::from(v) }
+    }
+
+    // Re-establish the invariant after an in-place operation, by removing trailing zeros.
+    fn normalize(&mut self) {
+        let len = trim_limbs(&self.data).len();
+        self.data.truncate(len);
     }
 
     /// Increments the number by 1.
@@ -115,8 +628,10 @@ impl BigInt {
                 idx += 1;
             }
         }
-        // If we came here, there is a last carry to add
-        self.data.push(by);
+        // If we came here, there is a last carry to add (unless we were asked to add 0 to 0)
+        if by != 0 {
+            self.data.push(by);
+        }
     }
 
     /// Return the nth power-of-2 as BigInt
@@ -129,163 +644,1454 @@ impl BigInt {
         v.push(1 << power);
         BigInt::from_vec(v)
     }
-}
-
-impl Clone for BigInt {
-    fn clone(&self) -> Self {
-        BigInt { data: self.data.clone() }
-    }
-}
 
-impl PartialEq for BigInt {
-    fn eq(&self, other: &BigInt) -> bool {
-        debug_assert!(self.test_invariant() && other.test_invariant());
-        self.data == other.data
+    /// Raise the number to the power `exp`.
+    pub fn pow(&self, mut exp: u32) -> BigInt {
+        // Square-and-multiply, going through the bits of the exponent from the least significant one.
+        let mut base = self.clone();
+        let mut result = BigInt::new(1);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result *= &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        result
     }
-}
 
-impl Minimum for BigInt {
-    // This is essentially the solution to 06.1.
-    fn min<'a>(&'a self, other: &'a Self) -> &'a Self {
-        debug_assert!(self.test_invariant() && other.test_invariant());
-        if self.data.len() < other.data.len() {
-            self
-        } else if self.data.len() > other.data.len() {
-            other
+    /// Compute `self^exp mod modulus`. Panics if `modulus` is zero.
+    pub fn modpow(&self, exp: &BigInt, modulus: &BigInt) -> BigInt {
+        if modulus.data.is_empty() {
+            panic!("Modular exponentiation of BigInt with modulus zero");
+        }
+        if *modulus == BigInt::new(1) {
+            return BigInt::new(0);
+        }
+        let base = self % modulus;
+        if modulus.data[0] % 2 == 1 {
+            // For odd moduli, we can do all the work in Montgomery form.
+            let mont = Montgomery::new(&modulus.data);
+            let base = mont.to_mont(&base);
+            let mut result = mont.one();
+            for bit in (0..exp.bit_length()).rev() {
+                result = mont.mul(&result, &result);
+                if exp.test_bit(bit) {
+                    result = mont.mul(&result, &base);
+                }
+            }
+            mont.from_mont(&result)
         } else {
-            // compare back-to-front, i.e., most significant digit first
-            let mut idx = self.data.len();
-            while idx > 0 {
-                idx = idx-1;
-                if self.data[idx] < other.data[idx] {
-                    return self;
-                } else if self.data[idx] > other.data[idx] {
-                    return other;
+            // Square-and-multiply, going through the bits of the exponent from the most significant one.
+            let mut result = BigInt::new(1);
+            for bit in (0..exp.bit_length()).rev() {
+                result = &result * &result % modulus;
+                if exp.test_bit(bit) {
+                    result = &result * &base % modulus;
                 }
             }
-            // the two are equal
-            return self;
+            result
         }
     }
-}
-
-impl fmt::Debug for BigInt {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.data.fmt(f)
-    }
-}
 
-impl<'a, 'b> ops::Add<&'a BigInt> for &'b BigInt {
-    type Output = BigInt;
-    fn add(self, rhs: &'a BigInt) -> Self::Output {
-        let max_len = cmp::max(self.data.len(), rhs.data.len());
-        let mut result_vec:Vec<u64> = Vec::with_capacity(max_len);
-        let mut carry:bool = false; // the carry bit
-        for i in 0..max_len {
-            // compute next digit and carry
-            let lhs_val = if i < self.data.len() { self.data[i] } else { 0 };
-            let rhs_val = if i < rhs.data.len() { rhs.data[i] } else { 0 };
-            let (sum, new_carry) = overflowing_add(lhs_val, rhs_val, carry);
-            // store them
-            result_vec.push(sum);
-            carry = new_carry;
+    /// Compute the greatest common divisor of the two numbers. The gcd of 0 and 0 is 0.
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        // Euclid's algorithm
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.data.is_empty() {
+            let r = &a % &b;
+            a = b;
+            b = r;
         }
-        if carry {
-            result_vec.push(1);
-        }
-        // We know that the invariant holds: overflowing_add would only return (0, false) if
-        // the arguments are (0, 0, false), but we know that in the last iteration, one od the two digits
-        // is the last of its number and hence not 0.
-        BigInt { data: result_vec }
+        a
     }
-}
 
-impl<'a> ops::Add<BigInt> for &'a BigInt {
-    type Output = BigInt;
-    #[inline]
-    fn add(self, rhs: BigInt) -> Self::Output {
-        self + &rhs
+    /// Compute the greatest common divisor `g` of the two numbers, together with coefficients `x` and `y`
+    /// such that `self*x + other*y = g`.
+    pub fn extended_gcd(&self, other: &BigInt) -> (BigInt, SignedBigInt, SignedBigInt) {
+        // Extended Euclidean algorithm: We maintain `self*s + other*t = r` for both the old and the new row.
+        let (mut old_r, mut r) = (self.clone(), other.clone());
+        let (mut old_s, mut s) = (SignedBigInt::from(1i64), SignedBigInt::from(0i64));
+        let (mut old_t, mut t) = (SignedBigInt::from(0i64), SignedBigInt::from(1i64));
+        while !r.data.is_empty() {
+            let (quotient, remainder) = old_r.div_rem(&r);
+            let quotient = SignedBigInt::from(quotient);
+            old_r = ::std::mem::replace(&mut r, remainder);
+            let new_s = &old_s - &quotient * &s;
+            old_s = ::std::mem::replace(&mut s, new_s);
+            let new_t = &old_t - &quotient * &t;
+            old_t = ::std::mem::replace(&mut t, new_t);
+        }
+        (old_r, old_s, old_t)
     }
-}
 
-impl<'a> ops::Add<&'a BigInt> for BigInt {
-    type Output = BigInt;
-    #[inline]
-    fn add(self, rhs: &'a BigInt) -> Self::Output {
-        &self + rhs
+    /// Compute the inverse of the number modulo `modulus`, i.e., the `x < modulus` with `self*x mod modulus = 1`.
+    /// Returns `None` if the number and `modulus` are not coprime, or `modulus` is zero.
+    pub fn mod_inverse(&self, modulus: &BigInt) -> Option<BigInt> {
+        if modulus.data.is_empty() {
+            return None;
+        }
+        let (gcd, x, _) = (self % modulus).extended_gcd(modulus);
+        if gcd != BigInt::new(1) {
+            return None;
+        }
+        // `x` may be negative, so move it into the right range.
+        let x = x.rem_euclid(&SignedBigInt::from(modulus.clone()));
+        Some(x.magnitude().clone())
     }
-}
 
-impl ops::Add<BigInt> for BigInt {
-    type Output = BigInt;
-    #[inline]
-    fn add(self, rhs: BigInt) -> Self::Output {
-        &self + &rhs
+    /// Divide by `other`, returning the quotient and the remainder. Panics if `other` is zero.
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        match self.checked_div_rem(other) {
+            Some(result) => result,
+            None => panic!("Division of BigInt by zero"),
+        }
     }
-}
 
-impl<'a, 'b> ops::Sub<&'a BigInt> for &'b BigInt {
-    type Output = BigInt;
-    fn sub(self, rhs: &'a BigInt) -> Self::Output {
-        let max_len = cmp::max(self.data.len(), rhs.data.len());
-        let mut result_vec:Vec<u64> = Vec::with_capacity(max_len);
-        let mut carry:bool = false; // the carry bit
-        for i in 0..max_len {
-            // compute next digit and carry
-            let lhs_val = if i < self.data.len() { self.data[i] } else { 0 };
-            let rhs_val = if i < rhs.data.len() { rhs.data[i] } else { 0 };
-            let (sum, new_carry) = overflowing_sub(lhs_val, rhs_val, carry);
-            // store them
-            result_vec.push(sum);
-            carry = new_carry;
+    /// Divide by `other`, returning the quotient and the remainder, or `None` if `other` is zero.
+    pub fn checked_div_rem(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        debug_assert!(self.test_invariant() && other.test_invariant());
+        if other.data.is_empty() {
+            return None;
         }
-        if carry {
-            panic!("Wrapping subtraction of BigInt");
+        if cmp_limbs(&self.data, &other.data) == cmp::Ordering::Less {
+            return Some((BigInt::new(0), self.clone()));
         }
-        // We may have trailing zeroes, so get rid of them
-        BigInt::from_vec(result_vec)
+        let (quotient, remainder) = if other.data.len() == 1 {
+            // Fast path for single-digit divisors
+            let (quotient, remainder) = div_rem_digit(&self.data, other.data[0]);
+            (quotient, vec![remainder])
+        } else {
+            div_rem_limbs(&self.data, &other.data)
+        };
+        Some((BigInt::from_vec(quotient), BigInt::from_vec(remainder)))
     }
-}
 
-impl<'a> ops::Sub<BigInt> for &'a BigInt {
-    type Output = BigInt;
-    #[inline]
-    fn sub(self, rhs: BigInt) -> Self::Output {
-        self - &rhs
+    /// Divide by `other`, or return `None` if `other` is zero.
+    pub fn checked_div(&self, other: &BigInt) -> Option<BigInt> {
+        self.checked_div_rem(other).map(|(quotient, _)| quotient)
     }
-}
 
-impl<'a> ops::Sub<&'a BigInt> for BigInt {
-    type Output = BigInt;
-    #[inline]
-    fn sub(self, rhs: &'a BigInt) -> Self::Output {
-        &self - rhs
+    /// Compute the remainder of division by `other`, or return `None` if `other` is zero.
+    pub fn checked_rem(&self, other: &BigInt) -> Option<BigInt> {
+        self.checked_div_rem(other).map(|(_, remainder)| remainder)
     }
 }
 
-impl ops::Sub<BigInt> for BigInt {
-    type Output = BigInt;
-    #[inline]
-    fn sub(self, rhs: BigInt) -> Self::Output {
-        &self - &rhs
-    }
-}
+// Helpers for the constant-time operations on `Uint`. They never branch on or index memory by the values of their
+// operands.
 
+// In test builds, count the steps of the constant-time loops, so that we can check that they do not depend on
+// the data.
 #[cfg(test)]
-mod tests {
-    use std::u64;
-    use super::{overflowing_add,overflowing_sub,BigInt,Minimum,vec_min};
+thread_local! {
+    pub(crate) static CT_STEPS: ::std::cell::Cell<u64> = ::std::cell::Cell::new(0);
+}
 
-    #[test]
-    fn test_min() {
-        let b1 = BigInt::new(1);
-        let b2 = BigInt::new(42);
-        let b3 = BigInt::from_vec(vec![0, 1]);
+#[inline]
+pub(crate) fn ct_step() {
+    #[cfg(test)]
+    CT_STEPS.with(|steps| steps.set(steps.get() + 1));
+}
+
+// Subtract with borrow, without branching. `borrow` is 0 or 1, and so is the returned borrow.
+pub(crate) fn ct_sub_borrow(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let diff = (a as u128).wrapping_sub(b as u128).wrapping_sub(borrow as u128);
+    (diff as u64, (diff >> 127) as u64)
+}
 
-        assert_eq!(b1.min(&b2), &b1);
-        assert_eq!(b2.min(&b1), &b1);
-        assert_eq!(b3.min(&b2), &b2);
-        assert_eq!(b2.min(&b3), &b2);
+// Set `dst` to `a` if `choice` is 1, and to `b` if `choice` is 0.
+pub(crate) fn ct_select_limbs(dst: &mut [u64], a: &[u64], b: &[u64], choice: u64) {
+    let mask = choice.wrapping_neg();
+    for ((d, &x), &y) in dst.iter_mut().zip(a).zip(b) {
+        ct_step();
+        *d = (x & mask) | (y & !mask);
+    }
+}
+
+impl BigInt {
+    /// Generate a random number with at most `bits` bits, i.e., uniformly distributed between 0 and `2^bits - 1`.
+    pub fn random_bits(bits: u64, rng: &mut XorShiftRng) -> BigInt {
+        let digits = ((bits + 63) / 64) as usize;
+        let mut data: Vec<u64> = (0..digits).map(|_| rng.next_u64()).collect();
+        let excess_bits = digits as u64 * 64 - bits;
+        if excess_bits > 0 {
+            // Clear the excess bits of the last digit.
+            data[digits - 1] >>= excess_bits;
+        }
+        BigInt::from_vec(data)
+    }
+
+    /// Generate a random number uniformly distributed between 0 and `bound - 1`. Panics if `bound` is zero.
+    pub fn random_below(bound: &BigInt, rng: &mut XorShiftRng) -> BigInt {
+        assert!(!bound.data.is_empty(), "Cannot generate a random BigInt below zero");
+        // Rejection sampling: We draw numbers with as many bits as `bound` until we get one that is small enough.
+        // Each attempt succeeds with probability more than 1/2.
+        let bits = bound.bit_length();
+        loop {
+            let candidate = BigInt::random_bits(bits, rng);
+            if candidate < *bound {
+                return candidate;
+            }
+        }
+    }
+}
+
+// Primes below this bound are used for trial division before running Miller-Rabin.
+const TRIAL_DIVISION_BOUND: u64 = 1000;
+// The number of Miller-Rabin rounds used by `next_prime`. Each round lets a composite number pass with a
+// probability of at most 1/4.
+const NEXT_PRIME_ROUNDS: u32 = 32;
+
+// Compute all primes below `bound`, using the sieve of Eratosthenes.
+fn small_primes(bound: u64) -> Vec<u64> {
+    let mut is_composite = vec![false; bound as usize];
+    let mut primes = Vec::new();
+    for n in 2..bound {
+        if !is_composite[n as usize] {
+            primes.push(n);
+            let mut multiple = n * n;
+            while multiple < bound {
+                is_composite[multiple as usize] = true;
+                multiple += n;
+            }
+        }
+    }
+    primes
+}
+
+impl BigInt {
+    /// Test whether the number is prime, using trial division followed by `rounds` rounds of the Miller-Rabin test.
+    /// If this returns `false`, the number is definitely composite; if it returns `true`, the number is prime with
+    /// probability at least `1 - 4^(-rounds)`. The witnesses are taken from a generator with a fixed seed, so the
+    /// result is always the same for the same input.
+    pub fn is_probable_prime(&self, rounds: u32) -> bool {
+        self.is_probable_prime_with_rng(rounds, &mut XorShiftRng::new(0))
+    }
+
+    /// Like `is_probable_prime`, but taking the Miller-Rabin witnesses from the given generator.
+    pub fn is_probable_prime_with_rng(&self, rounds: u32, rng: &mut XorShiftRng) -> bool {
+        self.is_probable_prime_with(rounds, rng, &small_primes(TRIAL_DIVISION_BOUND))
+    }
+
+    // The test itself, with the primes below `TRIAL_DIVISION_BOUND` passed in, so that `next_prime` only has to
+    // compute them once for all the candidates it tries.
+    fn is_probable_prime_with(&self, rounds: u32, rng: &mut XorShiftRng, primes: &[u64]) -> bool {
+        // Trial division. This also takes care of all the numbers that are too small for Miller-Rabin.
+        for &p in primes {
+            if self.data.len() <= 1 && self.data.first().cloned().unwrap_or(0) <= p {
+                return self.data.first() == Some(&p);
+            }
+            if rem_digit(&self.data, p) == 0 {
+                return false;
+            }
+        }
+        if *self < BigInt::new(TRIAL_DIVISION_BOUND * TRIAL_DIVISION_BOUND) {
+            // Any composite number this small has a prime factor below the bound.
+            return true;
+        }
+
+        // Write `self - 1 = d * 2^s` with `d` odd.
+        let n_1 = self - &BigInt::new(1);
+        let s = n_1.trailing_zeros().unwrap();
+        let d = &n_1 >> s;
+        // We work in Montgomery form, which is fine since `self` is odd by now.
+        let mont = Montgomery::new(&self.data);
+        let one = mont.one();
+        let minus_one = mont.to_mont(&n_1);
+        let witness_range = self - &BigInt::new(3);
+        'witness: for _ in 0..rounds {
+            // Pick a witness between 2 and `self - 2`.
+            let witness = BigInt::random_below(&witness_range, rng) + BigInt::new(2);
+            // Compute `witness^d`.
+            let base = mont.to_mont(&witness);
+            let mut x = one.clone();
+            for bit in (0..d.bit_length()).rev() {
+                x = mont.mul(&x, &x);
+                if d.test_bit(bit) {
+                    x = mont.mul(&x, &base);
+                }
+            }
+            if x == one || x == minus_one {
+                continue;
+            }
+            // Square up to `s-1` times. For a prime, we have to hit -1 on the way to `witness^(self-1) = 1`.
+            for _ in 1..s {
+                x = mont.mul(&x, &x);
+                if x == minus_one {
+                    continue 'witness;
+                }
+            }
+            return false;
+        }
+        true
+    }
+
+    /// Return the smallest prime that is larger than the number (with the same certainty as `is_probable_prime`
+    /// with 32 rounds).
+    pub fn next_prime(&self) -> BigInt {
+        if *self < BigInt::new(2) {
+            return BigInt::new(2);
+        }
+        // Start at the next odd number, and go on from there in steps of 2.
+        let mut candidate = self + &BigInt::new(if self.test_bit(0) { 2 } else { 1 });
+        let primes = small_primes(TRIAL_DIVISION_BOUND);
+        while !candidate.is_probable_prime_with(NEXT_PRIME_ROUNDS, &mut XorShiftRng::new(0), &primes) {
+            candidate.inc(2);
+        }
+        candidate
+    }
+}
+
+impl BigInt {
+    /// Compute the integer square root, i.e., the largest number whose square is not larger than `self`.
+    pub fn isqrt(&self) -> BigInt {
+        self.nth_root(2)
+    }
+
+    /// Compute the integer `n`-th root, i.e., the largest number whose `n`-th power is not larger than `self`.
+    /// Panics if `n` is zero.
+    pub fn nth_root(&self, n: u32) -> BigInt {
+        assert!(n > 0, "The 0th root of a BigInt is not defined");
+        if n == 1 || self.data.is_empty() {
+            return self.clone();
+        }
+        // The number is less than `2^n`, so the root is less than 2. Newton iteration would compute `2^(n-1)` here.
+        if n as u64 >= self.bit_length() {
+            return BigInt::new(1);
+        }
+        // Newton iteration, starting with a power of 2 that is not smaller than the root. As long as we are above
+        // the root, each step gets us closer, without dropping below it. So the first time we do not make
+        // progress any more, we are done.
+        let n_big = BigInt::new(n as u64);
+        let n_1 = BigInt::new(n as u64 - 1);
+        let mut x = BigInt::power_of_2((self.bit_length() + n as u64 - 1) / n as u64);
+        loop {
+            let y = (&n_1 * &x + self / &x.pow(n - 1)) / &n_big;
+            if y >= x {
+                return x;
+            }
+            x = y;
+        }
+    }
+
+    /// Compute the logarithm to base 2, rounded down. Panics if the number is zero.
+    pub fn ilog2(&self) -> u64 {
+        assert!(!self.data.is_empty(), "argument of integer logarithm must be positive");
+        self.bit_length() - 1
+    }
+
+    /// Compute the logarithm to base 10, rounded down. Panics if the number is zero.
+    pub fn ilog10(&self) -> u64 {
+        // 1233/4096 is slightly less than log10(2), so this is a lower bound for the result.
+        let mut log = (self.ilog2() * 1233) >> 12;
+        let mut power = BigInt::new(10).pow(log as u32 + 1);
+        while power <= *self {
+            log += 1;
+            power *= 10;
+        }
+        log
+    }
+}
+
+impl BigInt {
+    /// Return the bytes of the number, least significant byte first, without any trailing zero bytes. In particular,
+    /// zero is represented by no bytes at all.
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.data.len() * 8);
+        for digit in self.data.iter() {
+            bytes.extend_from_slice(&digit.to_le_bytes());
+        }
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        bytes
+    }
+
+    /// Return the bytes of the number, most significant byte first, without any leading zero bytes. In particular,
+    /// zero is represented by no bytes at all.
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let mut bytes = self.to_bytes_le();
+        bytes.reverse();
+        bytes
+    }
+
+    /// Return exactly `len` bytes of the number, most significant byte first, padded with zero bytes at the front.
+    /// Fails if the number does not fit into `len` bytes.
+    pub fn to_bytes_be_padded(&self, len: usize) -> Result<Vec<u8>, TryFromBigIntError> {
+        let bytes = self.to_bytes_be();
+        if bytes.len() > len {
+            return Err(TryFromBigIntError::new());
+        }
+        let mut result = vec![0; len - bytes.len()];
+        result.extend(bytes);
+        Ok(result)
+    }
+
+    /// Construct a number from its bytes, least significant byte first.
+    pub fn from_bytes_le(bytes: &[u8]) -> BigInt {
+        let data = bytes.chunks(8).map(|chunk| {
+            // The last chunk may be shorter.
+            let mut digit = [0; 8];
+            digit[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(digit)
+        }).collect();
+        // Leading zero bytes may have produced zero digits at the end.
+        BigInt::from_vec(data)
+    }
+
+    /// Construct a number from its bytes, most significant byte first.
+    pub fn from_bytes_be(bytes: &[u8]) -> BigInt {
+        let data = bytes.rchunks(8).map(|chunk| {
+            // The last chunk may be shorter.
+            let mut digit = [0; 8];
+            digit[8 - chunk.len()..].copy_from_slice(chunk);
+            u64::from_be_bytes(digit)
+        }).collect();
+        // Leading zero bytes may have produced zero digits at the end.
+        BigInt::from_vec(data)
+    }
+}
+
+impl Clone for BigInt {
+    fn clone(&self) -> Self {
+        BigInt { data: self.data.clone() }
+    }
+}
+
+impl PartialEq for BigInt {
+    fn eq(&self, other: &BigInt) -> bool {
+        debug_assert!(self.test_invariant() && other.test_invariant());
+        self.data[..] == other.data[..]
+    }
+}
+
+impl Eq for BigInt {}
+
+impl Ord for BigInt {
+    // This is essentially the solution to 06.1: Shorter numbers are smaller, and numbers of the same length
+    // are compared most significant digit first.
+    fn cmp(&self, other: &BigInt) -> cmp::Ordering {
+        debug_assert!(self.test_invariant() && other.test_invariant());
+        cmp_limbs(&self.data, &other.data)
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// `Minimum` agrees with `Ord`. If the two are equal, we return the first.
+impl Minimum for BigInt {
+    fn min<'a>(&'a self, other: &'a Self) -> &'a Self {
+        if self <= other { self } else { other }
+    }
+}
+
+// Thanks to our invariant, equal numbers have equal digits, so we can just hash those.
+impl hash::Hash for BigInt {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.data[..].hash(state)
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.data[..].fmt(f)
+    }
+}
+
+// The largest power of 10 that fits into a digit. We convert to decimal in chunks of this size.
+const DECIMAL_CHUNK: u64 = 10_000_000_000_000_000_000;
+const DECIMAL_CHUNK_DIGITS: usize = 19;
+
+impl BigInt {
+    // Write the number in a base that is a power of 2, using `bits` bits per (textual) digit.
+    fn to_pow2_radix_string(&self, bits: u32, upper: bool) -> String {
+        let total_bits = match self.data.last() {
+            None => return "0".to_string(),
+            Some(last) => self.data.len() * 64 - last.leading_zeros() as usize,
+        };
+        let mask = (1 << bits) - 1;
+        let mut digits = Vec::with_capacity(total_bits / bits as usize + 1);
+        let mut pos = 0;
+        while pos < total_bits {
+            // The textual digit may be spread over two of our digits.
+            let (idx, offset) = (pos / 64, (pos % 64) as u32);
+            let mut chunk = self.data[idx] >> offset;
+            if offset + bits > 64 && idx + 1 < self.data.len() {
+                chunk |= self.data[idx + 1] << (64 - offset);
+            }
+            let digit = ::std::char::from_digit((chunk & mask) as u32, 1 << bits).unwrap();
+            digits.push(if upper { digit.to_ascii_uppercase() } else { digit });
+            pos += bits as usize;
+        }
+        digits.iter().rev().collect()
+    }
+
+    // Write the number in base 10.
+    fn to_decimal_string(&self) -> String {
+        // Split the number into chunks of 19 decimal digits, least significant chunk first.
+        let mut chunks = Vec::new();
+        let mut rest = self.data.to_vec();
+        while !rest.is_empty() {
+            chunks.push(div_rem_digit_in_place(&mut rest, DECIMAL_CHUNK));
+            let len = trim_limbs(&rest).len();
+            rest.truncate(len);
+        }
+        let mut result = String::with_capacity(chunks.len() * DECIMAL_CHUNK_DIGITS);
+        match chunks.pop() {
+            None => result.push('0'),
+            Some(first) => result.push_str(&first.to_string()),
+        }
+        for chunk in chunks.iter().rev() {
+            result.push_str(&format!("{:01$}", chunk, DECIMAL_CHUNK_DIGITS));
+        }
+        result
+    }
+}
+
+// `pad_integral` takes care of width, fill, sign-aware zero padding and the alternate prefix for us.
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(true, "", &self.to_decimal_string())
+    }
+}
+
+impl fmt::LowerHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(true, "0x", &self.to_pow2_radix_string(4, false))
+    }
+}
+
+impl fmt::UpperHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(true, "0x", &self.to_pow2_radix_string(4, true))
+    }
+}
+
+impl fmt::Octal for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(true, "0o", &self.to_pow2_radix_string(3, false))
+    }
+}
+
+impl fmt::Binary for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(true, "0b", &self.to_pow2_radix_string(1, false))
+    }
+}
+
+/// The error returned when parsing a `BigInt` from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBigIntError {
+    /// There were no digits to parse.
+    Empty,
+    /// The character `found` at byte offset `position` is not a digit in the requested radix.
+    InvalidDigit { position: usize, found: char },
+}
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseBigIntError::Empty => write!(f, "cannot parse BigInt from a string without digits"),
+            ParseBigIntError::InvalidDigit { position, found } =>
+                write!(f, "invalid digit {:?} at position {} while parsing BigInt", found, position),
+        }
+    }
+}
+
+impl error::Error for ParseBigIntError {}
+
+// Multiply the digits by `factor` and add `summand`, in place, returning the digit that carries over.
+fn mul_digit_add(data: &mut [u64], factor: u64, summand: u64) -> u64 {
+    let mut carry = summand;
+    for digit in data.iter_mut() {
+        let (low, high) = mul_add(0, *digit, factor, carry);
+        *digit = low;
+        carry = high;
+    }
+    carry
+}
+
+impl BigInt {
+    /// Parse a number written in the given `radix`, which has to be between 2 and 36. The number may start with
+    /// a `+`, and digits may be separated by `_`. For radix 16, 8 and 2 we also accept the prefixes `0x`, `0o` and
+    /// `0b`, respectively.
+    pub fn from_str_radix(src: &str, radix: u32) -> Result<BigInt, ParseBigIntError> {
+        assert!((2..=36).contains(&radix), "from_str_radix: radix must lie in the range `[2, 36]`, not {}", radix);
+        let mut start = if src.starts_with('+') { 1 } else { 0 };
+        let prefix = match radix {
+            16 => Some("0x"),
+            8 => Some("0o"),
+            2 => Some("0b"),
+            _ => None,
+        };
+        if let Some(prefix) = prefix {
+            if src[start..].starts_with(prefix) {
+                start += prefix.len();
+            }
+        }
+
+        // We collect as many textual digits as fit into one of our digits, and then add them all at once.
+        let mut chunk_size = 0;
+        let mut chunk_factor: u64 = 1;
+        while let Some(factor) = chunk_factor.checked_mul(radix as u64) {
+            chunk_factor = factor;
+            chunk_size += 1;
+        }
+
+        let mut data = Vec::new();
+        let (mut chunk, mut chunk_len, mut has_digits) = (0, 0, false);
+        for (position, c) in src[start..].char_indices() {
+            if c == '_' {
+                continue;
+            }
+            let digit = match c.to_digit(radix) {
+                Some(digit) => digit,
+                None => return Err(ParseBigIntError::InvalidDigit { position: start + position, found: c }),
+            };
+            has_digits = true;
+            chunk = chunk * radix as u64 + digit as u64;
+            chunk_len += 1;
+            if chunk_len == chunk_size {
+                let carry = mul_digit_add(&mut data, chunk_factor, chunk);
+                if carry != 0 {
+                    data.push(carry);
+                }
+                chunk = 0;
+                chunk_len = 0;
+            }
+        }
+        if !has_digits {
+            return Err(ParseBigIntError::Empty);
+        }
+        if chunk_len > 0 {
+            let carry = mul_digit_add(&mut data, (radix as u64).pow(chunk_len), chunk);
+            if carry != 0 {
+                data.push(carry);
+            }
+        }
+        Ok(BigInt::from_vec(data))
+    }
+}
+
+/// Parses decimal numbers, or hexadecimal, octal and binary numbers if they start with `0x`, `0o` or `0b`.
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+    fn from_str(s: &str) -> Result<BigInt, ParseBigIntError> {
+        let digits = s.strip_prefix('+').unwrap_or(s);
+        let radix = if digits.starts_with("0x") {
+            16
+        } else if digits.starts_with("0o") {
+            8
+        } else if digits.starts_with("0b") {
+            2
+        } else {
+            10
+        };
+        BigInt::from_str_radix(s, radix)
+    }
+}
+
+/// The error returned when a number does not fit into the target type of a conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TryFromBigIntError(());
+
+impl TryFromBigIntError {
+    pub(crate) fn new() -> Self {
+        TryFromBigIntError(())
+    }
+}
+
+impl fmt::Display for TryFromBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "out of range conversion of BigInt attempted")
+    }
+}
+
+impl error::Error for TryFromBigIntError {}
+
+impl From<u128> for BigInt {
+    fn from(x: u128) -> Self {
+        BigInt::from_vec(vec![x as u64, (x >> 64) as u64])
+    }
+}
+
+impl TryFrom<BigInt> for u128 {
+    type Error = TryFromBigIntError;
+    fn try_from(b: BigInt) -> Result<u128, TryFromBigIntError> {
+        match b.data.len() {
+            0 => Ok(0),
+            1 => Ok(b.data[0] as u128),
+            2 => Ok(((b.data[1] as u128) << 64) | (b.data[0] as u128)),
+            _ => Err(TryFromBigIntError::new()),
+        }
+    }
+}
+
+// All the smaller unsigned types convert via `u64` and `u128`, respectively.
+macro_rules! impl_primitive_conversions {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for BigInt {
+                fn from(x: $t) -> Self {
+                    BigInt::new(x as u64)
+                }
+            }
+
+            impl TryFrom<BigInt> for $t {
+                type Error = TryFromBigIntError;
+                fn try_from(b: BigInt) -> Result<$t, TryFromBigIntError> {
+                    let x = u128::try_from(b)?;
+                    <$t>::try_from(x).map_err(|_| TryFromBigIntError::new())
+                }
+            }
+        )*
+    }
+}
+
+impl_primitive_conversions!(u8, u16, u32, u64, usize);
+
+impl BigInt {
+    /// Convert the number to the nearest floating-point number (rounding ties to even), which may be infinity.
+    pub fn to_f64(&self) -> f64 {
+        let bits = self.bit_length();
+        if bits <= 64 {
+            // The conversion from `u64` already rounds correctly.
+            return self.data.first().cloned().unwrap_or(0) as f64;
+        }
+        if bits > 1024 {
+            return f64::INFINITY;
+        }
+        // Take the 64 most significant bits. If any of the bits we drop is set, we set the lowest of the remaining
+        // bits instead: That is way below the 53 bits that fit into an `f64`, so all that matters for rounding is
+        // whether it is set at all.
+        let shift = bits - 64;
+        let mut top = (self >> shift).data[0];
+        if self.trailing_zeros().unwrap() < shift {
+            top |= 1;
+        }
+        // Scaling by a power of 2 is exact (or overflows to infinity, as it should).
+        let scale = f64::from_bits((shift + 1023) << 52);
+        top as f64 * scale
+    }
+
+    /// Convert a floating-point number to a `BigInt`, rounding towards zero. Returns `None` for NaN, infinity and
+    /// numbers that are negative even after rounding.
+    pub fn from_f64(x: f64) -> Option<BigInt> {
+        if !x.is_finite() {
+            return None;
+        }
+        let x = x.trunc();
+        if x < 0.0 {
+            return None;
+        }
+        if x < 18446744073709551616.0 {
+            // This fits into `u64`, so the conversion is exact.
+            return Some(BigInt::new(x as u64));
+        }
+        // Now `x` is a (normal) integer with 53 bits of mantissa and a large exponent.
+        let bits = x.to_bits();
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let exponent = ((bits >> 52) & 0x7ff) - 1075;
+        Some(BigInt::new(mantissa) << exponent)
+    }
+}
+
+impl<'a, 'b> ops::Add<&'a BigInt> for &'b BigInt {
+    type Output = BigInt;
+    fn add(self, rhs: &'a BigInt) -> Self::Output {
+        let max_len = cmp::max(self.data.len(), rhs.data.len());
+        let mut result_vec = LimbVec::with_capacity(max_len + 1);
+        let mut carry:bool = false; // the carry bit
+        for i in 0..max_len {
+            // compute next digit and carry
+            let lhs_val = if i < self.data.len() { self.data[i] } else { 0 };
+            let rhs_val = if i < rhs.data.len() { rhs.data[i] } else { 0 };
+            let (sum, new_carry) = overflowing_add(lhs_val, rhs_val, carry);
+            // store them
+            result_vec.push(sum);
+            carry = new_carry;
+        }
+        if carry {
+            result_vec.push(1);
+        }
+        // We know that the invariant holds: overflowing_add would only return (0, false) if
+        // the arguments are (0, 0, false), but we know that in the last iteration, one od the two digits
+        // is the last of its number and hence not 0.
+        BigInt { data: result_vec }
+    }
+}
+
+impl<'a> ops::Add<BigInt> for &'a BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn add(self, rhs: BigInt) -> Self::Output {
+        self + &rhs
+    }
+}
+
+impl<'a> ops::Add<&'a BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn add(mut self, rhs: &'a BigInt) -> Self::Output {
+        // We own the left operand, so we can re-use its buffer.
+        self += rhs;
+        self
+    }
+}
+
+impl ops::Add<BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn add(self, rhs: BigInt) -> Self::Output {
+        self + &rhs
+    }
+}
+
+impl<'a, 'b> ops::Sub<&'a BigInt> for &'b BigInt {
+    type Output = BigInt;
+    fn sub(self, rhs: &'a BigInt) -> Self::Output {
+        let max_len = cmp::max(self.data.len(), rhs.data.len());
+        let mut result_vec = LimbVec::with_capacity(max_len);
+        let mut carry:bool = false; // the carry bit
+        for i in 0..max_len {
+            // compute next digit and carry
+            let lhs_val = if i < self.data.len() { self.data[i] } else { 0 };
+            let rhs_val = if i < rhs.data.len() { rhs.data[i] } else { 0 };
+            let (sum, new_carry) = overflowing_sub(lhs_val, rhs_val, carry);
+            // store them
+            result_vec.push(sum);
+            carry = new_carry;
+        }
+        if carry {
+            panic!("Wrapping subtraction of BigInt");
+        }
+        // We may have trailing zeroes, so get rid of them
+        let mut result = BigInt { data: result_vec };
+        result.normalize();
+        result
+    }
+}
+
+impl<'a> ops::Sub<BigInt> for &'a BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn sub(self, rhs: BigInt) -> Self::Output {
+        self - &rhs
+    }
+}
+
+impl<'a> ops::Sub<&'a BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn sub(mut self, rhs: &'a BigInt) -> Self::Output {
+        // We own the left operand, so we can re-use its buffer.
+        self -= rhs;
+        self
+    }
+}
+
+impl ops::Sub<BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn sub(self, rhs: BigInt) -> Self::Output {
+        self - &rhs
+    }
+}
+
+impl<'a, 'b> ops::Mul<&'a BigInt> for &'b BigInt {
+    type Output = BigInt;
+    fn mul(self, rhs: &'a BigInt) -> Self::Output {
+        // `from_vec` takes care of the high digits that ended up being 0.
+        BigInt::from_vec(mul_limbs(&self.data, &rhs.data))
+    }
+}
+
+impl<'a> ops::Mul<BigInt> for &'a BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn mul(self, rhs: BigInt) -> Self::Output {
+        self * &rhs
+    }
+}
+
+impl<'a> ops::Mul<&'a BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn mul(self, rhs: &'a BigInt) -> Self::Output {
+        &self * rhs
+    }
+}
+
+impl ops::Mul<BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn mul(self, rhs: BigInt) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+impl<'a, 'b> ops::Div<&'a BigInt> for &'b BigInt {
+    type Output = BigInt;
+    fn div(self, rhs: &'a BigInt) -> Self::Output {
+        self.div_rem(rhs).0
+    }
+}
+
+impl<'a> ops::Div<BigInt> for &'a BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn div(self, rhs: BigInt) -> Self::Output {
+        self / &rhs
+    }
+}
+
+impl<'a> ops::Div<&'a BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn div(self, rhs: &'a BigInt) -> Self::Output {
+        &self / rhs
+    }
+}
+
+impl ops::Div<BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn div(self, rhs: BigInt) -> Self::Output {
+        &self / &rhs
+    }
+}
+
+impl<'a, 'b> ops::Rem<&'a BigInt> for &'b BigInt {
+    type Output = BigInt;
+    fn rem(self, rhs: &'a BigInt) -> Self::Output {
+        self.div_rem(rhs).1
+    }
+}
+
+impl<'a> ops::Rem<BigInt> for &'a BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn rem(self, rhs: BigInt) -> Self::Output {
+        self % &rhs
+    }
+}
+
+impl<'a> ops::Rem<&'a BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn rem(self, rhs: &'a BigInt) -> Self::Output {
+        &self % rhs
+    }
+}
+
+impl ops::Rem<BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn rem(self, rhs: BigInt) -> Self::Output {
+        &self % &rhs
+    }
+}
+
+impl BigInt {
+    /// Return the number of bits needed to represent the number, i.e., the position of the highest set bit plus 1.
+    /// Zero has bit length 0.
+    pub fn bit_length(&self) -> u64 {
+        match self.data.last() {
+            None => 0,
+            Some(last) => self.data.len() as u64 * 64 - last.leading_zeros() as u64,
+        }
+    }
+
+    /// Return the number of bits that are set.
+    pub fn count_ones(&self) -> u64 {
+        self.data.iter().map(|digit| digit.count_ones() as u64).sum()
+    }
+
+    /// Return the position of the lowest set bit, or `None` if the number is zero.
+    pub fn trailing_zeros(&self) -> Option<u64> {
+        let idx = self.data.iter().position(|&digit| digit != 0)?;
+        Some(idx as u64 * 64 + self.data[idx].trailing_zeros() as u64)
+    }
+
+    /// Return whether bit `n` (counting from the least significant bit) is set.
+    pub fn test_bit(&self, n: u64) -> bool {
+        let idx = (n / 64) as usize;
+        idx < self.data.len() && (self.data[idx] >> (n % 64)) & 1 == 1
+    }
+
+    /// Set bit `n` (counting from the least significant bit) to `value`.
+    pub fn set_bit(&mut self, n: u64, value: bool) {
+        let idx = (n / 64) as usize;
+        let mask = 1 << (n % 64);
+        if value {
+            if idx >= self.data.len() {
+                self.data.resize(idx + 1);
+            }
+            self.data[idx] |= mask;
+        } else if idx < self.data.len() {
+            self.data[idx] &= !mask;
+            // Clearing the highest bit may leave us with zero digits at the end.
+            self.normalize();
+        }
+        debug_assert!(self.test_invariant());
+    }
+}
+
+impl<'a> ops::Shl<u64> for &'a BigInt {
+    type Output = BigInt;
+    fn shl(self, bits: u64) -> Self::Output {
+        if self.data.is_empty() {
+            return BigInt::new(0);
+        }
+        // First shift by whole digits, then by the remaining bits.
+        let mut result = vec![0; (bits / 64) as usize];
+        result.extend(shl_limbs(&self.data, (bits % 64) as u32));
+        BigInt::from_vec(result)
+    }
+}
+
+impl ops::Shl<u64> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn shl(self, bits: u64) -> Self::Output {
+        &self << bits
+    }
+}
+
+impl<'a> ops::Shr<u64> for &'a BigInt {
+    type Output = BigInt;
+    fn shr(self, bits: u64) -> Self::Output {
+        let digits = bits / 64;
+        if digits >= self.data.len() as u64 {
+            return BigInt::new(0);
+        }
+        // First drop the whole digits, then shift by the remaining bits.
+        let mut result = self.data[digits as usize..].to_vec();
+        shr_limbs_in_place(&mut result, (bits % 64) as u32);
+        BigInt::from_vec(result)
+    }
+}
+
+impl ops::Shr<u64> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn shr(self, bits: u64) -> Self::Output {
+        &self >> bits
+    }
+}
+
+impl<'a, 'b> ops::BitAnd<&'a BigInt> for &'b BigInt {
+    type Output = BigInt;
+    fn bitand(self, rhs: &'a BigInt) -> Self::Output {
+        // Digits beyond the end of the shorter number are 0, and so is their conjunction.
+        let result_vec = self.data.iter().zip(rhs.data.iter()).map(|(a, b)| a & b).collect();
+        BigInt::from_vec(result_vec)
+    }
+}
+
+impl<'a> ops::BitAnd<BigInt> for &'a BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn bitand(self, rhs: BigInt) -> Self::Output {
+        self & &rhs
+    }
+}
+
+impl<'a> ops::BitAnd<&'a BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn bitand(self, rhs: &'a BigInt) -> Self::Output {
+        &self & rhs
+    }
+}
+
+impl ops::BitAnd<BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn bitand(self, rhs: BigInt) -> Self::Output {
+        &self & &rhs
+    }
+}
+
+impl<'a, 'b> ops::BitOr<&'a BigInt> for &'b BigInt {
+    type Output = BigInt;
+    fn bitor(self, rhs: &'a BigInt) -> Self::Output {
+        let (long, short) = if self.data.len() >= rhs.data.len() { (self, rhs) } else { (rhs, self) };
+        let mut result_vec = long.data.to_vec();
+        for (digit, other) in result_vec.iter_mut().zip(short.data.iter()) {
+            *digit |= *other;
+        }
+        // The longest number determines the last digit, so the invariant holds.
+        BigInt::from_vec(result_vec)
+    }
+}
+
+impl<'a> ops::BitOr<BigInt> for &'a BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn bitor(self, rhs: BigInt) -> Self::Output {
+        self | &rhs
+    }
+}
+
+impl<'a> ops::BitOr<&'a BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn bitor(self, rhs: &'a BigInt) -> Self::Output {
+        &self | rhs
+    }
+}
+
+impl ops::BitOr<BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn bitor(self, rhs: BigInt) -> Self::Output {
+        &self | &rhs
+    }
+}
+
+impl<'a, 'b> ops::BitXor<&'a BigInt> for &'b BigInt {
+    type Output = BigInt;
+    fn bitxor(self, rhs: &'a BigInt) -> Self::Output {
+        let (long, short) = if self.data.len() >= rhs.data.len() { (self, rhs) } else { (rhs, self) };
+        let mut result_vec = long.data.to_vec();
+        for (digit, other) in result_vec.iter_mut().zip(short.data.iter()) {
+            *digit ^= *other;
+        }
+        // The last digits may have cancelled out.
+        BigInt::from_vec(result_vec)
+    }
+}
+
+impl<'a> ops::BitXor<BigInt> for &'a BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn bitxor(self, rhs: BigInt) -> Self::Output {
+        self ^ &rhs
+    }
+}
+
+impl<'a> ops::BitXor<&'a BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn bitxor(self, rhs: &'a BigInt) -> Self::Output {
+        &self ^ rhs
+    }
+}
+
+impl ops::BitXor<BigInt> for BigInt {
+    type Output = BigInt;
+    #[inline]
+    fn bitxor(self, rhs: BigInt) -> Self::Output {
+        &self ^ &rhs
+    }
+}
+
+impl<'a> ops::AddAssign<&'a BigInt> for BigInt {
+    fn add_assign(&mut self, rhs: &'a BigInt) {
+        // Make room for all digits of `rhs`. We only add a digit for the carry if we need it, so that small
+        // numbers stay small.
+        let len = cmp::max(self.data.len(), rhs.data.len());
+        self.data.resize(len);
+        if add_limbs_carry(&mut self.data, &rhs.data) {
+            self.data.push(1);
+        }
+    }
+}
+
+impl ops::AddAssign<u64> for BigInt {
+    #[inline]
+    fn add_assign(&mut self, rhs: u64) {
+        self.inc(rhs);
+    }
+}
+
+impl<'a> ops::SubAssign<&'a BigInt> for BigInt {
+    fn sub_assign(&mut self, rhs: &'a BigInt) {
+        if *self < *rhs {
+            panic!("Wrapping subtraction of BigInt");
+        }
+        sub_limbs_into(&mut self.data, &rhs.data);
+        self.normalize();
+    }
+}
+
+impl ops::SubAssign<u64> for BigInt {
+    fn sub_assign(&mut self, rhs: u64) {
+        if self.data.len() <= 1 && self.data.first().cloned().unwrap_or(0) < rhs {
+            panic!("Wrapping subtraction of BigInt");
+        }
+        sub_limbs_into(&mut self.data, &[rhs]);
+        self.normalize();
+    }
+}
+
+impl<'a> ops::MulAssign<&'a BigInt> for BigInt {
+    fn mul_assign(&mut self, rhs: &'a BigInt) {
+        if rhs.data.len() == 1 {
+            // Multiplying by a single digit can be done in place.
+            *self *= rhs.data[0];
+        } else {
+            // Otherwise, we need a separate buffer for the result anyway.
+            self.data = LimbVec::from(mul_limbs(&self.data, &rhs.data));
+            self.normalize();
+        }
+    }
+}
+
+impl ops::MulAssign<u64> for BigInt {
+    fn mul_assign(&mut self, rhs: u64) {
+        if rhs == 0 {
+            self.data.clear();
+        } else {
+            let carry = mul_digit_add(&mut self.data, rhs, 0);
+            if carry != 0 {
+                self.data.push(carry);
+            }
+        }
+    }
+}
+
+impl ops::ShlAssign<u64> for BigInt {
+    fn shl_assign(&mut self, bits: u64) {
+        if self.data.is_empty() {
+            return;
+        }
+        // First shift by the bits within a digit, back-to-front, and then make room for the whole digits.
+        let shift = (bits % 64) as u32;
+        if shift > 0 {
+            let top = self.data[self.data.len() - 1] >> (64 - shift);
+            for idx in (1..self.data.len()).rev() {
+                self.data[idx] = (self.data[idx] << shift) | (self.data[idx - 1] >> (64 - shift));
+            }
+            self.data[0] <<= shift;
+            if top != 0 {
+                self.data.push(top);
+            }
+        }
+        let digits = (bits / 64) as usize;
+        self.data.insert_zeros_front(digits);
+    }
+}
+
+impl ops::ShrAssign<u64> for BigInt {
+    fn shr_assign(&mut self, bits: u64) {
+        let digits = cmp::min(bits / 64, self.data.len() as u64) as usize;
+        self.data.remove_front(digits);
+        shr_limbs_in_place(&mut self.data, (bits % 64) as u32);
+        self.normalize();
+    }
+}
+
+/// An iterator over the digits of a `BigInt`, least significant digit first. It can also go backwards, so use
+/// `rev` to get the most significant digit first.
+#[derive(Clone, Debug)]
+pub struct Limbs<'a> {
+    iter: slice::Iter<'a, u64>,
+}
+
+impl<'a> Iterator for Limbs<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.iter.next().cloned()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Limbs<'a> {
+    fn next_back(&mut self) -> Option<u64> {
+        self.iter.next_back().cloned()
+    }
+}
+
+impl<'a> ExactSizeIterator for Limbs<'a> {}
+
+/// An iterator that consumes a `BigInt` and yields its digits, least significant digit first.
+#[derive(Clone)]
+pub struct IntoLimbs {
+    data: LimbVec,
+    front: usize, // the index of the next digit to return from the front
+    back: usize, // one past the index of the next digit to return from the back
+}
+
+impl Iterator for IntoLimbs {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.front == self.back {
+            None
+        } else {
+            self.front += 1;
+            Some(self.data[self.front - 1])
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for IntoLimbs {
+    fn next_back(&mut self) -> Option<u64> {
+        if self.front == self.back {
+            None
+        } else {
+            self.back -= 1;
+            Some(self.data[self.back])
+        }
+    }
+}
+
+impl ExactSizeIterator for IntoLimbs {}
+
+/// Mutable access to the digits of a `BigInt`, least significant digit first. Since the digits may be changed
+/// arbitrarily, the invariant is restored when this is dropped.
+pub struct LimbsMut<'a> {
+    num: &'a mut BigInt,
+}
+
+impl<'a> ops::Deref for LimbsMut<'a> {
+    type Target = [u64];
+    fn deref(&self) -> &[u64] {
+        &self.num.data
+    }
+}
+
+impl<'a> ops::DerefMut for LimbsMut<'a> {
+    fn deref_mut(&mut self) -> &mut [u64] {
+        &mut self.num.data
+    }
+}
+
+impl<'a> Drop for LimbsMut<'a> {
+    fn drop(&mut self) {
+        // The most significant digits may have become 0.
+        self.num.normalize();
+    }
+}
+
+impl BigInt {
+    /// Iterate over the digits, least significant digit first.
+    pub fn limbs(&self) -> Limbs<'_> {
+        Limbs { iter: self.data.iter() }
+    }
+
+    /// Obtain mutable access to the digits, least significant digit first.
+    pub fn limbs_mut(&mut self) -> LimbsMut<'_> {
+        LimbsMut { num: self }
+    }
+}
+
+impl<'a> IntoIterator for &'a BigInt {
+    type Item = u64;
+    type IntoIter = Limbs<'a>;
+    fn into_iter(self) -> Limbs<'a> {
+        self.limbs()
+    }
+}
+
+impl IntoIterator for BigInt {
+    type Item = u64;
+    type IntoIter = IntoLimbs;
+    fn into_iter(self) -> IntoLimbs {
+        let len = self.data.len();
+        IntoLimbs { data: self.data, front: 0, back: len }
+    }
+}
+
+impl iter::Sum for BigInt {
+    fn sum<I: Iterator<Item = BigInt>>(iter: I) -> BigInt {
+        iter.fold(BigInt::new(0), |mut acc, x| { acc += &x; acc })
+    }
+}
+
+impl<'a> iter::Sum<&'a BigInt> for BigInt {
+    fn sum<I: Iterator<Item = &'a BigInt>>(iter: I) -> BigInt {
+        iter.fold(BigInt::new(0), |mut acc, x| { acc += x; acc })
+    }
+}
+
+impl iter::Product for BigInt {
+    fn product<I: Iterator<Item = BigInt>>(iter: I) -> BigInt {
+        iter.fold(BigInt::new(1), |mut acc, x| { acc *= &x; acc })
+    }
+}
+
+impl<'a> iter::Product<&'a BigInt> for BigInt {
+    fn product<I: Iterator<Item = &'a BigInt>>(iter: I) -> BigInt {
+        iter.fold(BigInt::new(1), |mut acc, x| { acc *= x; acc })
+    }
+}
+
+// Below this many factors, `product_range` just multiplies them one after the other.
+const PRODUCT_TREE_LEAF: u64 = 16;
+
+// Compute the product of all numbers in `lo..=hi`. We split the range in halves recursively, so that we multiply
+// numbers of similar size (which is where Karatsuba shines) instead of multiplying one huge and one tiny number
+// over and over. The bound is inclusive, so that we never have to compute `u64::MAX + 1`.
+fn product_range(lo: u64, hi: u64) -> BigInt {
+    if hi < lo {
+        return BigInt::new(1);
+    }
+    if hi - lo < PRODUCT_TREE_LEAF {
+        return (lo..=hi).map(BigInt::new).product();
+    }
+    let mid = lo + (hi - lo) / 2;
+    product_range(lo, mid) * product_range(mid + 1, hi)
+}
+
+impl BigInt {
+    /// Compute `n! = 1 * 2 * ... * n`.
+    pub fn factorial(n: u64) -> BigInt {
+        product_range(1, n)
+    }
+
+    /// Compute the binomial coefficient "n choose k", which is 0 if `k > n`.
+    pub fn binomial(n: u64, k: u64) -> BigInt {
+        if k > n {
+            return BigInt::new(0);
+        }
+        // n!/(k!(n-k)!) = ((n-k+1) * ... * n) / k!, and the smaller `k`, the less we have to compute.
+        let k = cmp::min(k, n - k);
+        if k == 0 {
+            return BigInt::new(1);
+        }
+        product_range(n - k + 1, n) / product_range(1, k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::u64;
+    use signed_bigint::SignedBigInt;
+    use rng::XorShiftRng;
+    use std::convert::TryFrom;
+    use std::{cmp, ops};
+    use super::{overflowing_add,overflowing_sub,mul_schoolbook_into,BigInt,LimbVec,Minimum,vec_min,DECIMAL_CHUNK,cmp_limbs,add_limbs_carry};
+
+    #[test]
+    fn test_min() {
+        let b1 = BigInt::new(1);
+        let b2 = BigInt::new(42);
+        let b3 = BigInt::from_vec(vec![0, 1]);
+
+        assert_eq!(Minimum::min(&b1, &b2), &b1);
+        assert_eq!(Minimum::min(&b2, &b1), &b1);
+        assert_eq!(Minimum::min(&b3, &b2), &b2);
+        assert_eq!(Minimum::min(&b2, &b3), &b2);
     }
 
     #[test]
@@ -375,6 +2181,17 @@ mod tests {
         assert_eq!(b, BigInt::from_vec(vec![1, 1]));
     }
 
+    #[test]
+    fn test_inc() {
+        let mut b = BigInt::new(0);
+        b.inc(0);
+        assert!(b.test_invariant());
+        b.inc(u64::MAX);
+        assert_eq!(b, BigInt::new(u64::MAX));
+        b.inc(2);
+        assert_eq!(b, BigInt::from_vec(vec![1, 1]));
+    }
+
     #[test]
     fn test_power_of_2() {
         assert_eq!(BigInt::power_of_2(0), BigInt::new(1));
@@ -383,6 +2200,937 @@ mod tests {
         assert_eq!(BigInt::power_of_2(96), BigInt::from_vec(vec![0, 1 << 32]));
         assert_eq!(BigInt::power_of_2(128), BigInt::from_vec(vec![0, 0, 1]));
     }
-}
 
+    // A simple linear congruential generator, to get some "random" digits for the tests.
+    fn test_digits(seed: u64, len: usize) -> Vec<u64> {
+        let mut state = seed;
+        (0..len).map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            state
+        }).collect()
+    }
+
+    #[test]
+    fn test_mul() {
+        let b1 = BigInt::new(1 << 32);
+        let b2 = BigInt::from_vec(vec![0, 1]);
+        let b3 = BigInt::new(u64::MAX);
+
+        assert_eq!(&b1 * &b1, b2);
+        assert_eq!(&b1 * &BigInt::new(0), BigInt::new(0));
+        assert_eq!(BigInt::new(0) * &b3, BigInt::new(0));
+        assert_eq!(&b2 * &b2, BigInt::power_of_2(128));
+        assert_eq!(&b3 * &b3, BigInt::from_vec(vec![1, u64::MAX - 1]));
+        assert_eq!(b3.clone() * BigInt::new(1), b3);
+        assert_eq!(&b3 * &b2 + &b3, BigInt::from_vec(vec![u64::MAX, u64::MAX]));
+    }
+
+    #[test]
+    fn test_mul_karatsuba() {
+        for &(len_a, len_b) in [(40, 40), (100, 70), (33, 200), (250, 250), (64, 1)].iter() {
+            let a = BigInt::from_vec(test_digits(len_a as u64, len_a));
+            let b = BigInt::from_vec(test_digits(len_b as u64 + 1000, len_b));
+            let mut expected = vec![0; len_a + len_b];
+            mul_schoolbook_into(&mut expected, &a.data, &b.data);
+            let product = &a * &b;
+            assert!(product.test_invariant());
+            assert_eq!(product, BigInt::from_vec(expected));
+            assert_eq!(product, &b * &a);
+        }
+        // (x+1)*(x-1) = x*x - 1, where the subtraction has to borrow across all digits
+        let x = BigInt::power_of_2(64 * 100);
+        let one = BigInt::new(1);
+        let product = (&x + &one) * (&x - &one);
+        assert!(product.test_invariant());
+        assert_eq!(product, &x * &x - &one);
+        assert_eq!(product.data.len(), 200);
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let b1 = BigInt::new(1 << 32);
+        let b2 = BigInt::from_vec(vec![0, 1]);
+        let b3 = BigInt::from_vec(vec![1, u64::MAX - 1]);
+
+        assert_eq!(&b2 / &b1, b1);
+        assert_eq!(&b2 % &b1, BigInt::new(0));
+        assert_eq!(BigInt::new(100).div_rem(&BigInt::new(7)), (BigInt::new(14), BigInt::new(2)));
+        assert_eq!(BigInt::new(5).div_rem(&b2), (BigInt::new(0), BigInt::new(5)));
+        assert_eq!(b3.div_rem(&BigInt::new(u64::MAX)), (BigInt::new(u64::MAX), BigInt::new(0)));
+        assert_eq!((&b3 + &b1) / BigInt::new(u64::MAX), BigInt::new(u64::MAX));
+        assert_eq!((&b3 + &b1) % BigInt::new(u64::MAX), b1);
+        assert_eq!(BigInt::power_of_2(200) / BigInt::power_of_2(130), BigInt::power_of_2(70));
+        assert_eq!(BigInt::power_of_2(200) % BigInt::power_of_2(130), BigInt::new(0));
+        assert_eq!(b3.checked_div(&BigInt::new(0)), None);
+        assert_eq!(b3.checked_rem(&b3), Some(BigInt::new(0)));
+    }
+
+    #[test]
+    fn test_div_rem_multi_digit() {
+        let mut divisors = vec![
+            BigInt::from_vec(vec![u64::MAX, u64::MAX]),
+            BigInt::from_vec(vec![0, 1 << 63]),
+            BigInt::from_vec(vec![1, 0, 1 << 63]),
+            BigInt::from_vec(vec![u64::MAX, 1]),
+        ];
+        for len in 2..12 {
+            divisors.push(BigInt::from_vec(test_digits(len as u64 + 77, len)));
+        }
+        for len in 1..40 {
+            let a = BigInt::from_vec(test_digits(len as u64, len));
+            for b in divisors.iter() {
+                let (q, r) = a.div_rem(b);
+                assert!(q.test_invariant() && r.test_invariant());
+                assert!(r < *b);
+                assert_eq!(&q * b + &r, a);
+            }
+        }
+        // Dividing the product again gives back the factors.
+        let a = BigInt::from_vec(test_digits(3, 50));
+        let b = BigInt::from_vec(test_digits(4, 20));
+        assert_eq!((&a * &b).div_rem(&b), (a.clone(), BigInt::new(0)));
+        assert_eq!((&a * &b + BigInt::new(42)).div_rem(&a), (b, BigInt::new(42)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Division of BigInt by zero")]
+    fn test_div_by_zero() {
+        let _ = BigInt::new(1) / BigInt::new(0);
+    }
+
+    #[test]
+    fn test_fmt() {
+        let values: Vec<u128> = vec![0, 1, 7, 10, 255, 1 << 64, (1 << 64) - 1, 10_000_000_000_000_000_000,
+                                     123_456_789_012_345_678_901_234_567_890, u128::MAX];
+        for &v in values.iter() {
+            let b = BigInt::from_vec(vec![v as u64, (v >> 64) as u64]);
+            assert_eq!(format!("{}", b), format!("{}", v));
+            assert_eq!(format!("{:x}", b), format!("{:x}", v));
+            assert_eq!(format!("{:X}", b), format!("{:X}", v));
+            assert_eq!(format!("{:o}", b), format!("{:o}", v));
+            assert_eq!(format!("{:b}", b), format!("{:b}", v));
+            assert_eq!(format!("{:>45}", b), format!("{:>45}", v));
+            assert_eq!(format!("{:*<45}", b), format!("{:*<45}", v));
+            assert_eq!(format!("{:+045}", b), format!("{:+045}", v));
+            assert_eq!(format!("{:#x}", b), format!("{:#x}", v));
+            assert_eq!(format!("{:#040X}", b), format!("{:#040X}", v));
+            assert_eq!(format!("{:#o}", b), format!("{:#o}", v));
+            assert_eq!(format!("{:#b}", b), format!("{:#b}", v));
+        }
+    }
+
+    #[test]
+    fn test_fmt_large() {
+        let b = BigInt::power_of_2(300);
+        assert_eq!(format!("{}", b), "2037035976334486086268445688409378161051468393665936250636140449354381299763336706183397376");
+        assert_eq!(format!("{:x}", b), format!("1{}", "0".repeat(75)));
+        assert_eq!(format!("{:o}", b), format!("1{}", "0".repeat(100)));
+        assert_eq!(format!("{}", BigInt::power_of_2(64 * 3) - BigInt::new(1)),
+                   "6277101735386680763835789423207666416102355444464034512895");
+        // Chunks in the middle with leading zeros must be padded.
+        let b = BigInt::from_vec(vec![DECIMAL_CHUNK]) * BigInt::from_vec(vec![DECIMAL_CHUNK]) + BigInt::new(5);
+        assert_eq!(format!("{}", b), format!("1{}5", "0".repeat(37)));
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("0".parse(), Ok(BigInt::new(0)));
+        assert_eq!("+000042".parse(), Ok(BigInt::new(42)));
+        assert_eq!("18446744073709551616".parse(), Ok(BigInt::from_vec(vec![0, 1])));
+        assert_eq!("18_446_744_073_709_551_615".parse(), Ok(BigInt::new(u64::MAX)));
+        assert_eq!("0xffff_ffff_ffff_ffff_ffff".parse(), Ok(BigInt::from_vec(vec![u64::MAX, 0xffff])));
+        assert_eq!("0o777".parse(), Ok(BigInt::new(0o777)));
+        assert_eq!("0b1_0000_0000".parse(), Ok(BigInt::new(256)));
+        let b = BigInt::power_of_2(300) - BigInt::new(12345);
+        assert_eq!(b.to_string().parse(), Ok(b.clone()));
+        assert_eq!(BigInt::from_str_radix(&format!("{:x}", b), 16), Ok(b.clone()));
+        assert_eq!(BigInt::from_str_radix(&format!("{:#b}", b), 2), Ok(b.clone()));
+        assert_eq!(BigInt::from_str_radix(&format!("{:o}", b), 8), Ok(b.clone()));
+        assert_eq!(BigInt::from_str_radix("zz", 36), Ok(BigInt::new(36 * 36 - 1)));
+        assert_eq!(BigInt::from_str_radix("0b1", 16), Ok(BigInt::new(0xb1)));
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        use super::ParseBigIntError::*;
+        assert_eq!("".parse::<BigInt>(), Err(Empty));
+        assert_eq!("+".parse::<BigInt>(), Err(Empty));
+        assert_eq!("0x".parse::<BigInt>(), Err(Empty));
+        assert_eq!("__".parse::<BigInt>(), Err(Empty));
+        assert_eq!("12a4".parse::<BigInt>(), Err(InvalidDigit { position: 2, found: 'a' }));
+        assert_eq!("-5".parse::<BigInt>(), Err(InvalidDigit { position: 0, found: '-' }));
+        assert_eq!("0b102".parse::<BigInt>(), Err(InvalidDigit { position: 4, found: '2' }));
+        assert_eq!(BigInt::from_str_radix("1 2", 10), Err(InvalidDigit { position: 1, found: ' ' }));
+        assert_eq!(format!("{}", InvalidDigit { position: 3, found: 'x' }),
+                   "invalid digit 'x' at position 3 while parsing BigInt");
+    }
+
+    #[test]
+    fn test_ord() {
+        use std::collections::{BTreeMap, HashSet};
+
+        let b1 = BigInt::new(1);
+        let b2 = BigInt::new(42);
+        let b3 = BigInt::from_vec(vec![0, 1]);
+        let b4 = BigInt::from_vec(vec![5, 1]);
+        let b5 = BigInt::from_vec(vec![0, 0, 1]);
+
+        assert!(BigInt::new(0) < b1 && b1 < b2 && b2 < b3 && b3 < b4 && b4 < b5);
+        assert!(b4 >= b3.clone() && b4 <= b4.clone());
+        let mut v = vec![b4.clone(), b1.clone(), b5.clone(), b3.clone(), b2.clone(), b1.clone()];
+        v.sort();
+        assert_eq!(v, vec![b1.clone(), b1.clone(), b2.clone(), b3.clone(), b4.clone(), b5.clone()]);
+        assert_eq!(v.iter().max(), Some(&b5));
+        // `Ord` and `Minimum` agree.
+        for x in v.iter() {
+            for y in v.iter() {
+                assert_eq!(Minimum::min(x, y), ::std::cmp::min(x, y));
+            }
+        }
+
+        let mut map = BTreeMap::new();
+        map.insert(b3.clone(), "b3");
+        map.insert(b1.clone(), "b1");
+        assert_eq!(map.keys().next(), Some(&b1));
+        let set: HashSet<BigInt> = v.into_iter().collect();
+        assert_eq!(set.len(), 5);
+        assert!(set.contains(&(&b1 + &b4 - &b1)));
+    }
+
+    #[test]
+    fn test_u128_conversion() {
+        use std::convert::TryFrom;
+        for &v in [0, 1, u64::MAX as u128, 1 << 64, u128::MAX].iter() {
+            let b = BigInt::from(v);
+            assert!(b.test_invariant());
+            assert_eq!(u128::try_from(b), Ok(v));
+        }
+        assert!(u128::try_from(BigInt::power_of_2(128)).is_err());
+    }
+
+    #[test]
+    fn test_shifts() {
+        let b = BigInt::from_vec(vec![0x8000_0000_0000_0001, 3]);
+        assert_eq!(&b << 0, b);
+        assert_eq!(&b >> 0, b);
+        assert_eq!(&b << 1, BigInt::from_vec(vec![2, 7]));
+        assert_eq!(&b << 64, BigInt::from_vec(vec![0, 0x8000_0000_0000_0001, 3]));
+        assert_eq!(&b << 127, BigInt::from_vec(vec![0, 1 << 63, (1 << 63) | (1 << 62), 1]));
+        assert_eq!(&b >> 1, BigInt::from_vec(vec![(1 << 62) | (1 << 63), 1]));
+        assert_eq!(&b >> 64, BigInt::new(3));
+        assert_eq!(&b >> 65, BigInt::new(1));
+        assert_eq!(&b >> 66, BigInt::new(0));
+        assert_eq!(BigInt::new(0) << 100, BigInt::new(0));
+        assert_eq!(BigInt::new(1) << 200, BigInt::power_of_2(200));
+        for shift in 0..200 {
+            let shifted = &b << shift;
+            assert!(shifted.test_invariant());
+            assert_eq!(&shifted >> shift, b);
+            assert_eq!(shifted, &b * &BigInt::power_of_2(shift));
+            assert!((&b >> shift).test_invariant());
+            assert_eq!(&b >> shift, &b / &BigInt::power_of_2(shift));
+        }
+    }
+
+    #[test]
+    fn test_bitwise() {
+        let a = BigInt::from_vec(vec![0b1100, 0xff, 1]);
+        let b = BigInt::from_vec(vec![0b1010, 0xf0]);
+
+        assert_eq!(&a & &b, BigInt::from_vec(vec![0b1000, 0xf0]));
+        assert_eq!(&a | &b, BigInt::from_vec(vec![0b1110, 0xff, 1]));
+        assert_eq!(&a ^ &b, BigInt::from_vec(vec![0b0110, 0x0f, 1]));
+        assert_eq!(&b | &a, &a | &b);
+        // The high digits cancel out.
+        let c = &a ^ &a;
+        assert!(c.test_invariant());
+        assert_eq!(c, BigInt::new(0));
+        let d = BigInt::from_vec(vec![5, 1 << 40]) & BigInt::from_vec(vec![3, 1 << 41]);
+        assert!(d.test_invariant());
+        assert_eq!(d, BigInt::new(1));
+        let e = BigInt::from_vec(vec![5, 1 << 40]) ^ BigInt::from_vec(vec![3, 1 << 40]);
+        assert!(e.test_invariant());
+        assert_eq!(e, BigInt::new(6));
+    }
+
+    #[test]
+    fn test_bits() {
+        let mut b = BigInt::new(0);
+        assert_eq!(b.bit_length(), 0);
+        assert_eq!(b.count_ones(), 0);
+        assert_eq!(b.trailing_zeros(), None);
+        assert!(!b.test_bit(0) && !b.test_bit(1000));
+
+        b.set_bit(130, true);
+        assert_eq!(b, BigInt::power_of_2(130));
+        assert_eq!(b.bit_length(), 131);
+        assert_eq!(b.trailing_zeros(), Some(130));
+        b.set_bit(3, true);
+        assert!(b.test_bit(3) && b.test_bit(130) && !b.test_bit(4) && !b.test_bit(129));
+        assert_eq!(b.count_ones(), 2);
+        assert_eq!(b.trailing_zeros(), Some(3));
+        b.set_bit(130, false);
+        assert!(b.test_invariant());
+        assert_eq!(b, BigInt::new(8));
+        b.set_bit(500, false);
+        assert_eq!(b, BigInt::new(8));
+        b.set_bit(3, false);
+        assert!(b.test_invariant());
+        assert_eq!(b, BigInt::new(0));
+        assert_eq!(BigInt::new(u64::MAX).bit_length(), 64);
+        assert_eq!(BigInt::from_vec(vec![u64::MAX, u64::MAX]).count_ones(), 128);
+    }
+
+    #[test]
+    fn test_assign_ops() {
+        let values = [
+            BigInt::new(0), BigInt::new(1), BigInt::new(u64::MAX), BigInt::from_vec(vec![0, 1]),
+            BigInt::from_vec(vec![u64::MAX, u64::MAX]), BigInt::from_vec(test_digits(7, 40)),
+        ];
+        for a in values.iter() {
+            for b in values.iter() {
+                let mut c = a.clone();
+                c += b;
+                assert!(c.test_invariant());
+                assert_eq!(c, a + b);
+                c -= b;
+                assert!(c.test_invariant());
+                assert_eq!(c, *a);
+                c *= b;
+                assert!(c.test_invariant());
+                assert_eq!(c, a * b);
+            }
+            for &x in [0, 1, 2, u64::MAX].iter() {
+                let mut c = a.clone();
+                c += x;
+                assert!(c.test_invariant());
+                assert_eq!(c, a + BigInt::new(x));
+                c -= x;
+                assert!(c.test_invariant());
+                assert_eq!(c, *a);
+                c *= x;
+                assert!(c.test_invariant());
+                assert_eq!(c, a * BigInt::new(x));
+            }
+            for &shift in [0, 1, 63, 64, 65, 130].iter() {
+                let mut c = a.clone();
+                c <<= shift;
+                assert!(c.test_invariant());
+                assert_eq!(c, a << shift);
+                c >>= shift;
+                assert!(c.test_invariant());
+                assert_eq!(c, *a);
+                c >>= shift;
+                assert!(c.test_invariant());
+                assert_eq!(c, a >> shift);
+            }
+        }
+
+        let mut sum = BigInt::new(0);
+        for i in 0..1000 {
+            sum += &BigInt::from_vec(vec![u64::MAX, i]);
+        }
+        assert_eq!(sum, BigInt::from_vec(vec![u64::MAX - 999, 999 * 1000 / 2 + 999]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Wrapping subtraction of BigInt")]
+    fn test_sub_assign_panic() {
+        let mut b = BigInt::from_vec(vec![0, 1]);
+        b -= &BigInt::from_vec(vec![1, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Wrapping subtraction of BigInt")]
+    fn test_sub_assign_u64_panic() {
+        let mut b = BigInt::new(5);
+        b -= 6;
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_eq!(BigInt::new(0).pow(0), BigInt::new(1));
+        assert_eq!(BigInt::new(0).pow(5), BigInt::new(0));
+        assert_eq!(BigInt::new(3).pow(4), BigInt::new(81));
+        assert_eq!(BigInt::new(2).pow(200), BigInt::power_of_2(200));
+        assert_eq!(BigInt::power_of_2(3).pow(67), BigInt::power_of_2(201));
+        let b = BigInt::from_vec(test_digits(1, 3));
+        assert_eq!(b.pow(5), &b * &b * &b * &b * &b);
+    }
+
+    // Naive modular exponentiation, to compare against
+    fn modpow_naive(base: &BigInt, exp: u64, modulus: &BigInt) -> BigInt {
+        let mut result = BigInt::new(1) % modulus;
+        for _ in 0..exp {
+            result = &result * base % modulus;
+        }
+        result
+    }
+
+    #[test]
+    fn test_modpow() {
+        let moduli = [
+            BigInt::new(1), BigInt::new(2), BigInt::new(7), BigInt::new(1000), BigInt::new(u64::MAX),
+            BigInt::from_vec(vec![3, 1]), BigInt::from_vec(vec![0, 1]), BigInt::from_vec(test_digits(5, 4)),
+            BigInt::from_vec(test_digits(6, 3)) | BigInt::new(1),
+            BigInt::from_vec(test_digits(8, 3)) & BigInt::from_vec(vec![u64::MAX - 1, u64::MAX, u64::MAX]),
+        ];
+        let bases = [BigInt::new(0), BigInt::new(1), BigInt::new(3), BigInt::from_vec(test_digits(9, 5))];
+        for modulus in moduli.iter() {
+            for base in bases.iter() {
+                for &exp in [0, 1, 2, 7, 64, 100].iter() {
+                    let result = base.modpow(&BigInt::new(exp), modulus);
+                    assert!(result.test_invariant());
+                    assert_eq!(result, modpow_naive(base, exp, modulus));
+                }
+            }
+        }
+        // Fermat's little theorem for the Mersenne primes 2^61-1 and 2^127-1
+        for &p in [61, 127].iter() {
+            let p = BigInt::power_of_2(p) - BigInt::new(1);
+            let p_1 = &p - &BigInt::new(1);
+            assert_eq!(BigInt::new(3).modpow(&p_1, &p), BigInt::new(1));
+            assert_eq!(BigInt::from_vec(test_digits(10, 5)).modpow(&p, &p), BigInt::from_vec(test_digits(10, 5)) % &p);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Modular exponentiation of BigInt with modulus zero")]
+    fn test_modpow_zero() {
+        let _ = BigInt::new(3).modpow(&BigInt::new(3), &BigInt::new(0));
+    }
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(BigInt::new(0).gcd(&BigInt::new(0)), BigInt::new(0));
+        assert_eq!(BigInt::new(0).gcd(&BigInt::new(5)), BigInt::new(5));
+        assert_eq!(BigInt::new(12).gcd(&BigInt::new(18)), BigInt::new(6));
+        assert_eq!(BigInt::new(17).gcd(&BigInt::new(5)), BigInt::new(1));
+        let a = BigInt::from_vec(test_digits(11, 3));
+        let b = BigInt::from_vec(test_digits(12, 4));
+        let c = BigInt::from_vec(test_digits(13, 2));
+        let g = a.gcd(&b);
+        assert_eq!((&a * &c).gcd(&(&b * &c)), &g * &c);
+
+        for &(x, y) in [(240, 46), (46, 240), (0, 7), (7, 0), (17, 17), (1, 1 << 40)].iter() {
+            let (x, y) = (BigInt::new(x), BigInt::new(y));
+            let (g, s, t) = x.extended_gcd(&y);
+            assert_eq!(g, x.gcd(&y));
+            assert_eq!(SignedBigInt::from(x) * s + SignedBigInt::from(y) * t, SignedBigInt::from(g));
+        }
+        let (g, s, t) = (&a * &c).extended_gcd(&(&b * &c));
+        assert_eq!(SignedBigInt::from(&a * &c) * s + SignedBigInt::from(&b * &c) * t, SignedBigInt::from(g));
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(BigInt::new(3).mod_inverse(&BigInt::new(7)), Some(BigInt::new(5)));
+        assert_eq!(BigInt::new(10).mod_inverse(&BigInt::new(7)), Some(BigInt::new(5)));
+        assert_eq!(BigInt::new(4).mod_inverse(&BigInt::new(8)), None);
+        assert_eq!(BigInt::new(4).mod_inverse(&BigInt::new(0)), None);
+        assert_eq!(BigInt::new(4).mod_inverse(&BigInt::new(1)), Some(BigInt::new(0)));
+        let p = BigInt::power_of_2(127) - BigInt::new(1);
+        let a = BigInt::from_vec(test_digits(14, 2)) % &p;
+        let inv = a.mod_inverse(&p).unwrap();
+        assert!(inv < p);
+        assert_eq!(&a * &inv % &p, BigInt::new(1));
+    }
+
+    #[test]
+    fn test_is_probable_prime() {
+        use super::small_primes;
+
+        let primes = small_primes(20000);
+        for n in 0..20000 {
+            assert_eq!(BigInt::new(n).is_probable_prime(10), primes.binary_search(&n).is_ok(), "{}", n);
+        }
+        // Carmichael numbers and strong pseudoprimes to base 2
+        for &n in [561, 41041, 825265, 321197185, 2047, 3215031751, 3825123056546413051].iter() {
+            assert!(!BigInt::new(n).is_probable_prime(10), "{}", n);
+        }
+        // Mersenne primes, and the composite Fermat number 2^128+1
+        assert!(BigInt::new(2305843009213693951).is_probable_prime(10));
+        assert!((BigInt::power_of_2(127) - BigInt::new(1)).is_probable_prime(10));
+        assert!((BigInt::power_of_2(521) - BigInt::new(1)).is_probable_prime(10));
+        assert!(!(BigInt::power_of_2(128) + BigInt::new(1)).is_probable_prime(10));
+        assert!(!(BigInt::power_of_2(521) + BigInt::new(1)).is_probable_prime(10));
+        // The product of two large primes has no small factors.
+        let p = BigInt::power_of_2(89) - BigInt::new(1);
+        let q = BigInt::power_of_2(107) - BigInt::new(1);
+        assert!(!(&p * &q).is_probable_prime(10));
+        // The result does not depend on the seed for primes.
+        for seed in 0..10 {
+            assert!(p.is_probable_prime_with_rng(5, &mut XorShiftRng::new(seed)));
+        }
+    }
+
+    #[test]
+    fn test_next_prime() {
+        assert_eq!(BigInt::new(0).next_prime(), BigInt::new(2));
+        assert_eq!(BigInt::new(1).next_prime(), BigInt::new(2));
+        assert_eq!(BigInt::new(2).next_prime(), BigInt::new(3));
+        assert_eq!(BigInt::new(3).next_prime(), BigInt::new(5));
+        assert_eq!(BigInt::new(13).next_prime(), BigInt::new(17));
+        assert_eq!(BigInt::new(1000).next_prime(), BigInt::new(1009));
+        assert_eq!(BigInt::new(u64::MAX - 100).next_prime(), BigInt::new(u64::MAX - 94));
+        assert_eq!(BigInt::new(u64::MAX - 94).next_prime(), BigInt::new(u64::MAX - 82));
+        assert_eq!(BigInt::new(u64::MAX - 82).next_prime(), BigInt::new(u64::MAX - 58));
+        assert_eq!(BigInt::power_of_2(64).next_prime(), BigInt::power_of_2(64) + BigInt::new(13));
+        assert_eq!(BigInt::power_of_2(128).next_prime(), BigInt::power_of_2(128) + BigInt::new(51));
+    }
+
+    #[test]
+    fn test_roots() {
+        for x in 0u64..2000 {
+            let b = BigInt::new(x);
+            for n in 1..6 {
+                let root = b.nth_root(n);
+                let expected = (0u64..).take_while(|r| r.pow(n) <= x).last().unwrap();
+                assert_eq!(root, BigInt::new(expected), "{}-th root of {}", n, x);
+            }
+            assert_eq!(b.isqrt(), b.nth_root(2));
+        }
+        let x = BigInt::from_vec(test_digits(15, 7));
+        let one = BigInt::new(1);
+        let square = &x * &x;
+        assert_eq!(square.isqrt(), x);
+        assert_eq!((&square - &one).isqrt(), &x - &one);
+        assert_eq!((&square + &x + &x).isqrt(), x);
+        let cube = x.pow(3);
+        assert_eq!(cube.nth_root(3), x);
+        assert_eq!((&cube - &one).nth_root(3), &x - &one);
+        assert_eq!(x.pow(7).nth_root(7), x);
+        assert_eq!(BigInt::power_of_2(1000).nth_root(10), BigInt::power_of_2(100));
+        assert_eq!(BigInt::power_of_2(64).nth_root(100), BigInt::new(1));
+        assert_eq!(BigInt::new(1000).nth_root(u32::MAX), BigInt::new(1));
+        assert_eq!(BigInt::power_of_2(64).nth_root(64), BigInt::new(2));
+        assert_eq!(BigInt::power_of_2(64).nth_root(65), BigInt::new(1));
+    }
+
+    #[test]
+    fn test_logarithms() {
+        for &x in [1, 2, 3, 9, 10, 11, 99, 100, 1 << 40, 999_999_999_999, u64::MAX].iter() {
+            assert_eq!(BigInt::new(x).ilog2(), 63 - x.leading_zeros() as u64);
+            let (mut log10, mut rest) = (0, x / 10);
+            while rest > 0 {
+                log10 += 1;
+                rest /= 10;
+            }
+            assert_eq!(BigInt::new(x).ilog10(), log10);
+        }
+        assert_eq!(BigInt::power_of_2(1000).ilog2(), 1000);
+        assert_eq!((BigInt::power_of_2(1000) - BigInt::new(1)).ilog2(), 999);
+        for k in [19, 20, 100, 1000].iter() {
+            let power = BigInt::new(10).pow(*k);
+            assert_eq!(power.ilog10(), *k as u64);
+            assert_eq!((&power - &BigInt::new(1)).ilog10(), *k as u64 - 1);
+            assert_eq!(power.to_string().len() as u64, power.ilog10() + 1);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "argument of integer logarithm must be positive")]
+    fn test_ilog_zero() {
+        BigInt::new(0).ilog10();
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(BigInt::new(0).to_bytes_be(), Vec::<u8>::new());
+        assert_eq!(BigInt::new(0).to_bytes_le(), Vec::<u8>::new());
+        assert_eq!(BigInt::new(0x0102).to_bytes_be(), vec![1, 2]);
+        assert_eq!(BigInt::new(0x0102).to_bytes_le(), vec![2, 1]);
+        assert_eq!(BigInt::from_vec(vec![0x0102, 0x03]).to_bytes_be(), vec![3, 0, 0, 0, 0, 0, 0, 1, 2]);
+        assert_eq!(BigInt::from_vec(vec![0x0102, 0x03]).to_bytes_le(), vec![2, 1, 0, 0, 0, 0, 0, 0, 3]);
+        assert_eq!(BigInt::from_bytes_be(&[3, 0, 0, 0, 0, 0, 0, 1, 2]), BigInt::from_vec(vec![0x0102, 0x03]));
+        assert_eq!(BigInt::from_bytes_le(&[2, 1, 0, 0, 0, 0, 0, 0, 3]), BigInt::from_vec(vec![0x0102, 0x03]));
+        assert_eq!(BigInt::from_bytes_be(&[]), BigInt::new(0));
+        assert_eq!(BigInt::from_bytes_le(&[]), BigInt::new(0));
+
+        // Zero bytes in the wrong place must not break the invariant.
+        let b = BigInt::from_bytes_be(&[0; 20]);
+        assert!(b.test_invariant());
+        assert_eq!(b, BigInt::new(0));
+        let b = BigInt::from_bytes_be(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7]);
+        assert!(b.test_invariant());
+        assert_eq!(b, BigInt::new(7));
+        let b = BigInt::from_bytes_le(&[7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(b.test_invariant());
+        assert_eq!(b, BigInt::new(7));
+
+        for len in 0..30 {
+            let b = BigInt::from_vec(test_digits(len as u64, len)) >> (len as u64 * 3);
+            assert_eq!(BigInt::from_bytes_be(&b.to_bytes_be()), b);
+            assert_eq!(BigInt::from_bytes_le(&b.to_bytes_le()), b);
+            let be = b.to_bytes_be();
+            assert!(be.first() != Some(&0));
+            assert_eq!(be.iter().rev().cloned().collect::<Vec<u8>>(), b.to_bytes_le());
+        }
+    }
+
+    #[test]
+    fn test_bytes_padded() {
+        let b = BigInt::new(0x010203);
+        assert_eq!(b.to_bytes_be_padded(5), Ok(vec![0, 0, 1, 2, 3]));
+        assert_eq!(b.to_bytes_be_padded(3), Ok(vec![1, 2, 3]));
+        assert!(b.to_bytes_be_padded(2).is_err());
+        assert_eq!(BigInt::new(0).to_bytes_be_padded(0), Ok(vec![]));
+        assert_eq!(BigInt::new(0).to_bytes_be_padded(2), Ok(vec![0, 0]));
+        let b = BigInt::power_of_2(255);
+        let padded = b.to_bytes_be_padded(32).unwrap();
+        assert_eq!(padded[0], 0x80);
+        assert_eq!(BigInt::from_bytes_be(&padded), b);
+        assert!(BigInt::power_of_2(256).to_bytes_be_padded(32).is_err());
+    }
+
+    #[test]
+    fn test_primitive_conversions() {
+        use std::convert::TryFrom;
+        assert_eq!(BigInt::from(200u8), BigInt::new(200));
+        assert_eq!(BigInt::from(60000u16), BigInt::new(60000));
+        assert_eq!(BigInt::from(0u32), BigInt::new(0));
+        assert_eq!(BigInt::from(u64::MAX), BigInt::new(u64::MAX));
+        assert_eq!(BigInt::from(usize::MAX), BigInt::new(usize::MAX as u64));
+        assert_eq!(u8::try_from(BigInt::new(255)), Ok(255));
+        assert!(u8::try_from(BigInt::new(256)).is_err());
+        assert_eq!(u16::try_from(BigInt::new(65535)), Ok(65535));
+        assert!(u16::try_from(BigInt::new(65536)).is_err());
+        assert_eq!(u32::try_from(BigInt::new(0)), Ok(0));
+        assert!(u32::try_from(BigInt::new(1 << 32)).is_err());
+        assert_eq!(u64::try_from(BigInt::new(u64::MAX)), Ok(u64::MAX));
+        assert!(u64::try_from(BigInt::from_vec(vec![0, 1])).is_err());
+        assert_eq!(usize::try_from(BigInt::new(12345)), Ok(12345));
+        assert!(usize::try_from(BigInt::power_of_2(100)).is_err());
+    }
+
+    #[test]
+    fn test_to_f64() {
+        assert_eq!(BigInt::new(0).to_f64(), 0.0);
+        assert_eq!(BigInt::new(12345).to_f64(), 12345.0);
+        assert_eq!(BigInt::new(u64::MAX).to_f64(), u64::MAX as f64);
+        for &x in [1u128 << 64, (1 << 64) + 1, u128::MAX, ((1 << 53) - 1) << 70, ((1 << 53) + 1) << 70,
+                   (((1 << 53) + 1) << 70) + 1, ((1 << 54) - 1) << 70, 0x1234_5678_9abc_def0_1234_5678_9abc_def0].iter() {
+            assert_eq!(BigInt::from(x).to_f64(), x as f64, "{}", x);
+        }
+        assert_eq!(BigInt::power_of_2(1023).to_f64(), 2f64.powi(1023));
+        assert_eq!(BigInt::power_of_2(1024).to_f64(), f64::INFINITY);
+        // Rounds up to 2^1024, and hence to infinity.
+        assert_eq!((BigInt::power_of_2(1024) - BigInt::new(1)).to_f64(), f64::INFINITY);
+        assert_eq!((BigInt::power_of_2(1024) - BigInt::power_of_2(1024 - 53)).to_f64(), f64::MAX);
+        // Ties round to even, but a set bit far below the tie rounds up.
+        let tie = BigInt::power_of_2(200) + BigInt::power_of_2(200 - 53);
+        assert_eq!(tie.to_f64(), 2f64.powi(200));
+        assert_eq!((&tie + &BigInt::new(1)).to_f64(), 2f64.powi(200) + 2f64.powi(200 - 52));
+    }
+
+    #[test]
+    fn test_from_f64() {
+        assert_eq!(BigInt::from_f64(0.0), Some(BigInt::new(0)));
+        assert_eq!(BigInt::from_f64(-0.0), Some(BigInt::new(0)));
+        assert_eq!(BigInt::from_f64(-0.9), Some(BigInt::new(0)));
+        assert_eq!(BigInt::from_f64(3.99), Some(BigInt::new(3)));
+        assert_eq!(BigInt::from_f64(1e19), Some(BigInt::new(10_000_000_000_000_000_000)));
+        assert_eq!(BigInt::from_f64(2f64.powi(64)), Some(BigInt::power_of_2(64)));
+        assert_eq!(BigInt::from_f64(2f64.powi(1000) * 1.5), Some(BigInt::power_of_2(1000) + BigInt::power_of_2(999)));
+        assert_eq!(BigInt::from_f64(1e300).map(|b| b.to_f64()), Some(1e300));
+        assert_eq!(BigInt::from_f64(f64::MAX).map(|b| b.to_f64()), Some(f64::MAX));
+        assert_eq!(BigInt::from_f64(-1.0), None);
+        assert_eq!(BigInt::from_f64(f64::NAN), None);
+        assert_eq!(BigInt::from_f64(f64::INFINITY), None);
+        assert_eq!(BigInt::from_f64(f64::NEG_INFINITY), None);
+    }
+
+    #[test]
+    fn test_limbs() {
+        let b = BigInt::from_vec(vec![1, 2, 3]);
+        assert_eq!(b.limbs().collect::<Vec<u64>>(), vec![1, 2, 3]);
+        assert_eq!(b.limbs().rev().collect::<Vec<u64>>(), vec![3, 2, 1]);
+        assert_eq!(b.limbs().len(), 3);
+        let mut limbs = b.limbs();
+        assert_eq!(limbs.next(), Some(1));
+        assert_eq!(limbs.next_back(), Some(3));
+        assert_eq!(limbs.len(), 1);
+        assert_eq!(limbs.next_back(), Some(2));
+        assert_eq!(limbs.next(), None);
+        assert_eq!(limbs.next_back(), None);
+        assert_eq!(BigInt::new(0).limbs().len(), 0);
+
+        let mut sum = 0;
+        for digit in &b {
+            sum += digit;
+        }
+        assert_eq!(sum, 6);
+        let mut into_limbs = b.clone().into_iter();
+        assert_eq!(into_limbs.len(), 3);
+        assert_eq!(into_limbs.next_back(), Some(3));
+        assert_eq!(into_limbs.collect::<Vec<u64>>(), vec![1, 2]);
+        assert_eq!(BigInt::from_vec(b.into_iter().rev().collect()), BigInt::from_vec(vec![3, 2, 1]));
+    }
+
+    #[test]
+    fn test_limbs_mut() {
+        let mut b = BigInt::from_vec(vec![1, 2, 3]);
+        {
+            let mut limbs = b.limbs_mut();
+            limbs[0] = 10;
+            limbs[2] = 0;
+            assert_eq!(limbs.len(), 3);
+        }
+        assert!(b.test_invariant());
+        assert_eq!(b, BigInt::from_vec(vec![10, 2]));
+        for digit in b.limbs_mut().iter_mut() {
+            *digit = 0;
+        }
+        assert!(b.test_invariant());
+        assert_eq!(b, BigInt::new(0));
+    }
+
+    #[test]
+    fn test_sum_product() {
+        let values: Vec<BigInt> = (0..100).map(|i| BigInt::from_vec(vec![u64::MAX - i, i])).collect();
+        let mut expected = BigInt::new(0);
+        for v in values.iter() {
+            expected = &expected + v;
+        }
+        assert_eq!(values.iter().sum::<BigInt>(), expected);
+        assert_eq!(values.clone().into_iter().sum::<BigInt>(), expected);
+        assert_eq!(Vec::<BigInt>::new().into_iter().sum::<BigInt>(), BigInt::new(0));
+
+        let mut expected = BigInt::new(1);
+        for v in values.iter().skip(1) {
+            expected = &expected * v;
+        }
+        let product = values.iter().skip(1).product::<BigInt>();
+        assert!(product.test_invariant());
+        assert_eq!(product, expected);
+        assert_eq!(values.clone().into_iter().skip(1).product::<BigInt>(), expected);
+        assert_eq!(values.iter().chain(Some(&BigInt::new(0))).product::<BigInt>(), BigInt::new(0));
+        assert_eq!(Vec::<BigInt>::new().iter().product::<BigInt>(), BigInt::new(1));
+    }
+
+    #[test]
+    fn test_factorial() {
+        let mut expected = BigInt::new(1);
+        for n in 0..300 {
+            if n > 0 {
+                expected *= n;
+            }
+            assert_eq!(BigInt::factorial(n), expected);
+        }
+        assert_eq!(BigInt::factorial(20), BigInt::new(2432902008176640000));
+        assert_eq!(BigInt::factorial(1000).to_string().len(), 2568);
+    }
+
+    #[test]
+    fn test_binomial() {
+        // Pascal's triangle
+        let mut row = vec![BigInt::new(1)];
+        for n in 0..150u64 {
+            for k in 0..n + 3 {
+                let expected = row.get(k as usize).cloned().unwrap_or(BigInt::new(0));
+                assert_eq!(BigInt::binomial(n, k), expected, "{} choose {}", n, k);
+            }
+            let mut next = vec![BigInt::new(1)];
+            for k in 1..row.len() {
+                next.push(&row[k - 1] + &row[k]);
+            }
+            next.push(BigInt::new(1));
+            row = next;
+        }
+        assert_eq!(BigInt::binomial(1000, 500), BigInt::factorial(1000) / (BigInt::factorial(500) * BigInt::factorial(500)));
+        assert_eq!(BigInt::binomial(u64::MAX, 1), BigInt::new(u64::MAX));
+        assert_eq!(BigInt::binomial(u64::MAX, u64::MAX), BigInt::new(1));
+        assert_eq!(BigInt::binomial(u64::MAX, u64::MAX - 1), BigInt::new(u64::MAX));
+        assert_eq!(BigInt::binomial(u64::MAX, 2), BigInt::new(u64::MAX) * BigInt::new((u64::MAX - 1) / 2));
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = XorShiftRng::new(1);
+        for &bits in [0, 1, 5, 63, 64, 65, 200].iter() {
+            let mut max = BigInt::new(0);
+            for _ in 0..200 {
+                let b = BigInt::random_bits(bits, &mut rng);
+                assert!(b.test_invariant());
+                assert!(b.bit_length() <= bits);
+                max = ::std::cmp::max(max, b);
+            }
+            // With 200 draws, the highest bit is set at some point.
+            assert_eq!(max.bit_length(), bits);
+        }
+        let bound = BigInt::from_vec(vec![5, 1]);
+        for _ in 0..200 {
+            assert!(BigInt::random_below(&bound, &mut rng) < bound);
+        }
+        // All values below a small bound show up.
+        let mut seen = [false; 7];
+        for _ in 0..200 {
+            let b = BigInt::random_below(&BigInt::new(7), &mut rng);
+            seen[u64::try_from(b).unwrap() as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+        // Equal seeds give equal numbers.
+        assert_eq!(BigInt::random_bits(1000, &mut XorShiftRng::new(5)), BigInt::random_bits(1000, &mut XorShiftRng::new(5)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot generate a random BigInt below zero")]
+    fn test_random_below_zero() {
+        BigInt::random_below(&BigInt::new(0), &mut XorShiftRng::new(0));
+    }
+
+    // Property tests: Check algebraic laws on many random numbers of various sizes, including some that are large
+    // enough for Karatsuba.
+    fn random_operand(rng: &mut XorShiftRng) -> BigInt {
+        let bits = match rng.next_u64() % 4 {
+            0 => rng.next_u64() % 64,
+            1 => rng.next_u64() % 256,
+            2 => rng.next_u64() % 1024,
+            _ => rng.next_u64() % 4096,
+        };
+        BigInt::random_bits(bits, rng)
+    }
+
+    #[test]
+    fn test_ring_laws() {
+        let mut rng = XorShiftRng::new(2024);
+        for _ in 0..2000 {
+            let a = random_operand(&mut rng);
+            let b = random_operand(&mut rng);
+            let c = random_operand(&mut rng);
+            let sum = &a + &b;
+            let product = &a * &b;
+            assert!(sum.test_invariant() && product.test_invariant());
+            assert_eq!(&sum - &b, a);
+            assert_eq!(sum, &b + &a);
+            assert_eq!(product, &b * &a);
+            assert_eq!(&sum + &c, &a + &(&b + &c));
+            assert_eq!(&product * &c, &a * &(&b * &c));
+            assert_eq!(&a * &(&b + &c), &product + &(&a * &c));
+            assert_eq!(&a * &BigInt::new(1), a);
+            assert_eq!(&a + &BigInt::new(0), a);
+        }
+    }
+
+    #[test]
+    fn test_div_rem_laws() {
+        let mut rng = XorShiftRng::new(4711);
+        for _ in 0..2000 {
+            let a = random_operand(&mut rng);
+            let mut b = random_operand(&mut rng);
+            if b == BigInt::new(0) {
+                b = BigInt::new(1);
+            }
+            let (q, r) = a.div_rem(&b);
+            assert!(q.test_invariant() && r.test_invariant());
+            assert!(r < b);
+            assert_eq!(&q * &b + &r, a);
+            assert_eq!((&a * &b).div_rem(&b), (a.clone(), BigInt::new(0)));
+            assert_eq!((&a * &b + &r).div_rem(&b), (a.clone(), r.clone()));
+        }
+    }
+
+    #[test]
+    fn test_shift_and_bit_laws() {
+        let mut rng = XorShiftRng::new(99);
+        for _ in 0..2000 {
+            let a = random_operand(&mut rng);
+            let b = random_operand(&mut rng);
+            let shift = rng.next_u64() % 300;
+            assert_eq!(&a << shift, &a * &BigInt::power_of_2(shift));
+            assert_eq!(&a >> shift, &a / &BigInt::power_of_2(shift));
+            assert_eq!(&(&a & &b) + &(&a | &b), &a + &b);
+            assert_eq!(&a ^ &b, &(&a | &b) - &(&a & &b));
+            assert_eq!(a.to_string().parse(), Ok(a.clone()));
+            assert_eq!(BigInt::from_bytes_le(&a.to_bytes_le()), a);
+        }
+    }
+
+    fn is_inline(b: &BigInt) -> bool {
+        match b.data {
+            LimbVec::Inline(..) => true,
+            LimbVec::Heap(_) => false,
+        }
+    }
+
+    #[test]
+    fn test_inline_storage() {
+        let max = BigInt::new(u64::MAX);
+        assert!(is_inline(&BigInt::new(0)) && is_inline(&max));
+        assert!(is_inline(&BigInt::from_vec(vec![1, 2, 0, 0])));
+        // Sums of small numbers stay inline, until they need a third digit.
+        let mut sum = BigInt::new(0);
+        for _ in 0..1000 {
+            sum += &max;
+        }
+        assert!(is_inline(&sum));
+        assert_eq!(sum, BigInt::from_vec(vec![u64::MAX - 999, 999]));
+        let mut b = BigInt::from_vec(vec![u64::MAX, u64::MAX]);
+        b += 1;
+        assert!(!is_inline(&b) && b.test_invariant());
+        assert_eq!(b, BigInt::from_vec(vec![0, 0, 1]));
+        // Shifting moves digits across the boundary in both directions.
+        let mut c = BigInt::new(5);
+        c <<= 200;
+        assert_eq!(c, BigInt::power_of_2(200) * BigInt::new(5));
+        c >>= 190;
+        assert_eq!(c, BigInt::new(5 << 10));
+        assert!(c.test_invariant());
+        assert_eq!(b.into_iter().rev().collect::<Vec<_>>(), vec![1, 0, 0]);
+    }
+
+    // `BigInt` as it was before the inline storage: The digits always live in a `Vec` on the heap. Comparison and
+    // addition use the same limb helpers as `BigInt`, so the benchmark below measures just the storage.
+    struct HeapBigInt {
+        data: Vec<u64>,
+    }
+
+    impl Minimum for HeapBigInt {
+        fn min<'a>(&'a self, other: &'a Self) -> &'a Self {
+            if cmp_limbs(&self.data, &other.data) != cmp::Ordering::Greater { self } else { other }
+        }
+    }
+
+    impl<'a> ops::AddAssign<&'a HeapBigInt> for HeapBigInt {
+        fn add_assign(&mut self, rhs: &'a HeapBigInt) {
+            let len = cmp::max(self.data.len(), rhs.data.len());
+            self.data.resize(len, 0);
+            if add_limbs_carry(&mut self.data, &rhs.data) {
+                self.data.push(1);
+            }
+        }
+    }
+
+    // Compare the inline storage against one heap allocation per number, doing the same operations on both. This
+    // is not run by default; use `cargo test --release -- --ignored --nocapture` to see the timings.
+    #[test]
+    #[ignore]
+    fn bench_small_values() {
+        use std::time::Instant;
+        const COUNT: usize = 1_000_000;
+        let digits: Vec<u64> = test_digits(1, COUNT).into_iter().map(|d| d >> 8).collect();
+
+        let start = Instant::now();
+        let heap: Vec<HeapBigInt> = digits.iter().map(|&d| HeapBigInt { data: vec![d] }).collect();
+        println!("HeapBigInt: build {} values in {:?}", COUNT, start.elapsed());
+        let start = Instant::now();
+        let heap_min = vec_min(&heap);
+        println!("HeapBigInt: vec_min in {:?}", start.elapsed());
+        let start = Instant::now();
+        let heap_sum = heap.iter().fold(HeapBigInt { data: Vec::new() }, |mut acc, x| { acc += x; acc });
+        println!("HeapBigInt: sum in {:?}", start.elapsed());
+
+        let start = Instant::now();
+        let values: Vec<BigInt> = digits.iter().map(|&d| BigInt::new(d)).collect();
+        println!("BigInt: build {} values in {:?}", COUNT, start.elapsed());
+        let start = Instant::now();
+        let min = vec_min(&values);
+        println!("BigInt: vec_min in {:?}", start.elapsed());
+        let start = Instant::now();
+        let sum: BigInt = values.iter().sum();
+        println!("BigInt: sum in {:?}", start.elapsed());
 
+        assert_eq!(sum.data.to_vec(), heap_sum.data);
+        assert_eq!(min.map(|m| m.data.to_vec()), heap_min.map(|m| m.data.clone()));
+    }
+}
@@ -0,0 +1,150 @@
+use regex::{self, Regex};
+
+/// A shell-style glob pattern, as used in `.gitignore` files. It is matched against paths with `/` as separator.
+///
+/// `*` matches any sequence of characters except for `/`, `?` matches a single character except for `/`, and
+/// `[...]` is a character class (negated by `!` or `^`, and then still not matching `/`). `**` matches across
+/// directories when it forms a whole path component, as in `**/foo`, `foo/**` and `foo/**/bar`. A backslash makes
+/// the next character literal.
+#[derive(Clone, Debug)]
+pub struct Glob {
+    regex: Regex,
+}
+
+impl Glob {
+    /// Compile a glob. This cannot fail: An unclosed `[` is taken literally, and so is the entire glob if it is
+    /// invalid otherwise (like with a range `[z-a]`).
+    pub fn new(glob: &str) -> Glob {
+        let regex = Regex::new(&format!("^{}$", to_regex(glob)))
+            .unwrap_or_else(|_| Regex::new(&format!("^{}$", regex::escape(glob))).unwrap());
+        Glob { regex }
+    }
+
+    /// Return whether the whole of `path` matches the glob.
+    pub fn is_match(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+}
+
+// Push `c` to the regular expression such that it matches literally.
+fn push_literal(regex: &mut String, c: char) {
+    regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+}
+
+// Translate the glob to an (unanchored) regular expression.
+fn to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                // `**` is only special as a whole path component; otherwise, it is just like `*`.
+                let at_start = i == 0 || chars[i - 1] == '/';
+                let at_end = i + 2 == chars.len();
+                if at_start && chars.get(i + 2) == Some(&'/') {
+                    // `**/` matches any number of leading directories, including none.
+                    regex.push_str("(.*/)?");
+                    i += 3;
+                    continue;
+                } else if at_start && at_end {
+                    regex.push_str(".*");
+                } else {
+                    regex.push_str("[^/]*");
+                }
+                i += 2;
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                push_literal(&mut regex, chars[i]);
+            }
+            '[' => match class_to_regex(&chars[i + 1..]) {
+                Some((class, len)) => {
+                    regex.push_str(&class);
+                    i += len + 1;
+                    continue;
+                }
+                None => push_literal(&mut regex, '['),
+            },
+            c => push_literal(&mut regex, c),
+        }
+        i += 1;
+    }
+    regex
+}
+
+// Translate the character class in `chars`, which starts right after the `[`. Returns the class and the number of
+// characters it used up, or `None` if the class is not closed.
+fn class_to_regex(chars: &[char]) -> Option<(String, usize)> {
+    let mut class = String::from("[");
+    let mut i = 0;
+    if let Some(&'!') | Some(&'^') = chars.first() {
+        // Like `*` and `?`, a negated class never matches the separator.
+        class.push_str("^/");
+        i += 1;
+    }
+    let start = i;
+    loop {
+        match *chars.get(i)? {
+            // A `]` right at the beginning is taken literally.
+            ']' if i > start => break,
+            '\\' => {
+                i += 1;
+                let c = *chars.get(i)?;
+                // Our regular expressions do not know escapes of letters and digits, and they do not need one.
+                if !c.is_alphanumeric() {
+                    class.push('\\');
+                }
+                class.push(c);
+            }
+            c @ '[' | c @ ']' | c @ '^' => {
+                class.push('\\');
+                class.push(c);
+            }
+            c => class.push(c),
+        }
+        i += 1;
+    }
+    class.push(']');
+    Some((class, i + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Glob;
+
+    fn matches(glob: &str, path: &str) -> bool {
+        Glob::new(glob).is_match(path)
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches("*.rs", "main.rs") && !matches("*.rs", "main.rs.bak"));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("part??.rs", "part13.rs") && !matches("part??.rs", "part1.rs"));
+        assert!(matches("a.b", "a.b") && !matches("a.b", "axb"));
+        assert!(matches("(x)+{y}", "(x)+{y}"));
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(matches("part[0-9].rs", "part7.rs") && !matches("part[0-9].rs", "partx.rs"));
+        assert!(matches("[!.]*", "main.rs") && !matches("[!.]*", ".hidden"));
+        assert!(matches("[^a]", "b") && matches("[]]", "]") && matches("[a^]", "^"));
+        assert!(matches("[a", "[a") && matches(r"\*", "*") && !matches(r"\*", "x"));
+        assert!(matches(r"[\]x]", "]") && matches(r"[\d]", "d"));
+        assert!(matches("[z-a]", "[z-a]"));
+        assert!(!matches("a[!x]b", "a/b") && !matches("[!.]*", "src/main.rs") && matches("a[!/]b", "a-b"));
+    }
+
+    #[test]
+    fn test_double_star() {
+        assert!(matches("**/foo", "foo") && matches("**/foo", "a/b/foo") && !matches("**/foo", "a/xfoo"));
+        assert!(matches("foo/**", "foo/a/b") && !matches("foo/**", "bar/a"));
+        assert!(matches("a/**/b", "a/b") && matches("a/**/b", "a/x/y/b") && !matches("a/**/b", "a/xb"));
+        assert!(matches("a**b", "axxb") && !matches("a**b", "a/b"));
+    }
+}
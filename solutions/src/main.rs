@@ -6,6 +6,14 @@
 extern crate docopt;
 
 pub mod bigint;
+pub mod signed_bigint;
+pub mod uint;
+pub mod ratio;
+// The regular expression engine is part of the course, which uses it for rgrep in part 13.
+#[path = "../../src/regex.rs"]
+pub mod regex;
+pub mod glob;
+pub mod rng;
 pub mod vec;
 pub mod rgrep;
 pub mod callbacks;
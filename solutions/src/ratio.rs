@@ -0,0 +1,372 @@
+use std::ops;
+use std::cmp;
+use std::fmt;
+use std::error;
+use std::str::FromStr;
+use bigint::{BigInt, ParseBigIntError};
+use signed_bigint::SignedBigInt;
+
+/// An exact fraction of two arbitrary-precision integers. It is always stored in lowest terms, with a positive
+/// denominator, so that every rational number has exactly one representation.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Ratio {
+    numer: SignedBigInt, // carries the sign
+    denom: BigInt, // never 0, and has no common factor with `numer`
+}
+
+impl Ratio {
+    /// Construct the fraction `numer/denom`, reducing it to lowest terms. Panics if `denom` is zero.
+    pub fn new(numer: SignedBigInt, denom: BigInt) -> Self {
+        if denom == BigInt::new(0) {
+            panic!("Ratio with denominator zero");
+        }
+        let gcd = numer.magnitude().gcd(&denom);
+        let magnitude = numer.magnitude() / &gcd;
+        Ratio { numer: SignedBigInt::from_parts(numer.is_negative(), magnitude), denom: denom / gcd }
+    }
+
+    fn test_invariant(&self) -> bool {
+        self.denom != BigInt::new(0) && self.numer.magnitude().gcd(&self.denom) == BigInt::new(1)
+    }
+
+    /// Return the numerator, which carries the sign of the fraction.
+    pub fn numer(&self) -> &SignedBigInt {
+        &self.numer
+    }
+
+    /// Return the denominator, which is always positive.
+    pub fn denom(&self) -> &BigInt {
+        &self.denom
+    }
+
+    /// Return whether the fraction is an integer.
+    pub fn is_integer(&self) -> bool {
+        self.denom == BigInt::new(1)
+    }
+
+    // Compute `self * 10^places`, rounded to the nearest integer. Halfway cases are rounded away from zero,
+    // like `f64::round` does.
+    fn round_scaled(&self, places: u32) -> SignedBigInt {
+        let scaled = self.numer.magnitude() * &BigInt::new(10).pow(places);
+        let (mut quotient, remainder) = scaled.div_rem(&self.denom);
+        if &remainder + &remainder >= self.denom {
+            quotient += 1;
+        }
+        SignedBigInt::from_parts(self.numer.is_negative(), quotient)
+    }
+
+    /// Round to `places` decimal places. Halfway cases are rounded away from zero.
+    pub fn round(&self, places: u32) -> Ratio {
+        Ratio::new(self.round_scaled(places), BigInt::new(10).pow(places))
+    }
+
+    /// Round to `places` decimal places, and format the result in decimal notation, e.g. `"-1.50"`.
+    pub fn to_decimal_string(&self, places: u32) -> String {
+        let scaled = self.round_scaled(places);
+        let digits = format!("{:0>width$}", scaled.magnitude(), width = places as usize + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - places as usize);
+        let sign = if scaled.is_negative() { "-" } else { "" };
+        if places == 0 {
+            format!("{}{}", sign, integer)
+        } else {
+            format!("{}{}.{}", sign, integer, fraction)
+        }
+    }
+}
+
+impl From<SignedBigInt> for Ratio {
+    fn from(numer: SignedBigInt) -> Self {
+        Ratio { numer, denom: BigInt::new(1) }
+    }
+}
+
+impl From<BigInt> for Ratio {
+    fn from(numer: BigInt) -> Self {
+        Ratio::from(SignedBigInt::from(numer))
+    }
+}
+
+impl From<i64> for Ratio {
+    fn from(numer: i64) -> Self {
+        Ratio::from(SignedBigInt::from(numer))
+    }
+}
+
+impl Ord for Ratio {
+    // The denominators are positive, so we can compare `a/b` and `c/d` by comparing `a*d` and `c*b`.
+    fn cmp(&self, other: &Ratio) -> cmp::Ordering {
+        let lhs = &self.numer * &SignedBigInt::from(other.denom.clone());
+        let rhs = &other.numer * &SignedBigInt::from(self.denom.clone());
+        lhs.cmp(&rhs)
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Ratio) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&format!("{}/{}", self.numer, self.denom))
+    }
+}
+
+/// The error returned when parsing a `Ratio` from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseRatioError {
+    /// The numerator, denominator or decimal number had no digits.
+    Empty,
+    /// The character `found` at byte offset `position` is not valid at that place.
+    InvalidDigit { position: usize, found: char },
+    /// The denominator is zero.
+    ZeroDenominator,
+}
+
+impl fmt::Display for ParseRatioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseRatioError::Empty => write!(f, "cannot parse Ratio from a string without digits"),
+            ParseRatioError::InvalidDigit { position, found } =>
+                write!(f, "invalid digit {:?} at position {} while parsing Ratio", found, position),
+            ParseRatioError::ZeroDenominator => write!(f, "cannot parse Ratio with denominator zero"),
+        }
+    }
+}
+
+impl error::Error for ParseRatioError {}
+
+// Parse the unsigned decimal number `s`, which starts at byte offset `offset` of the whole input.
+fn parse_unsigned(s: &str, offset: usize) -> Result<BigInt, ParseRatioError> {
+    // `BigInt` would accept a leading `+`, but we only allow one sign, at the very beginning.
+    if let Some(found) = s.chars().next().filter(|c| !c.is_ascii_digit()) {
+        return Err(ParseRatioError::InvalidDigit { position: offset, found });
+    }
+    BigInt::from_str_radix(s, 10).map_err(|err| match err {
+        ParseBigIntError::Empty => ParseRatioError::Empty,
+        ParseBigIntError::InvalidDigit { position, found } =>
+            ParseRatioError::InvalidDigit { position: offset + position, found },
+    })
+}
+
+/// Parses fractions like `"-3/4"`, integers like `"42"` and decimal numbers like `"1.25"`.
+impl FromStr for Ratio {
+    type Err = ParseRatioError;
+    fn from_str(s: &str) -> Result<Ratio, ParseRatioError> {
+        let (negative, start) = match s.as_bytes().first() {
+            Some(&b'-') => (true, 1),
+            Some(&b'+') => (false, 1),
+            _ => (false, 0),
+        };
+        let rest = &s[start..];
+        let (numer, denom) = if let Some(slash) = rest.find('/') {
+            let numer = parse_unsigned(&rest[..slash], start)?;
+            let denom = parse_unsigned(&rest[slash + 1..], start + slash + 1)?;
+            if denom == BigInt::new(0) {
+                return Err(ParseRatioError::ZeroDenominator);
+            }
+            (numer, denom)
+        } else if let Some(dot) = rest.find('.') {
+            // `1.25` is `125/100`. Like for floats, one of the two sides of the dot may be empty.
+            let (integer, fraction) = (&rest[..dot], &rest[dot + 1..]);
+            if integer.is_empty() && fraction.is_empty() {
+                return Err(ParseRatioError::Empty);
+            }
+            // Separators like in `1.2_5` are skipped when parsing, so they must not count for the scale either.
+            let digits = fraction.chars().filter(|&c| c != '_').count();
+            let scale = BigInt::new(10).pow(digits as u32);
+            let integer = if integer.is_empty() { BigInt::new(0) } else { parse_unsigned(integer, start)? };
+            let fraction = if fraction.is_empty() { BigInt::new(0) } else { parse_unsigned(fraction, start + dot + 1)? };
+            (integer * &scale + fraction, scale)
+        } else {
+            (parse_unsigned(rest, start)?, BigInt::new(1))
+        };
+        Ok(Ratio::new(SignedBigInt::from_parts(negative, numer), denom))
+    }
+}
+
+impl<'a> ops::Neg for &'a Ratio {
+    type Output = Ratio;
+    fn neg(self) -> Self::Output {
+        Ratio { numer: -&self.numer, denom: self.denom.clone() }
+    }
+}
+
+impl ops::Neg for Ratio {
+    type Output = Ratio;
+    fn neg(self) -> Self::Output {
+        Ratio { numer: -self.numer, denom: self.denom }
+    }
+}
+
+impl<'a, 'b> ops::Add<&'a Ratio> for &'b Ratio {
+    type Output = Ratio;
+    fn add(self, rhs: &'a Ratio) -> Self::Output {
+        debug_assert!(self.test_invariant() && rhs.test_invariant());
+        let numer = &self.numer * &SignedBigInt::from(rhs.denom.clone())
+            + &rhs.numer * &SignedBigInt::from(self.denom.clone());
+        Ratio::new(numer, &self.denom * &rhs.denom)
+    }
+}
+
+impl<'a, 'b> ops::Sub<&'a Ratio> for &'b Ratio {
+    type Output = Ratio;
+    fn sub(self, rhs: &'a Ratio) -> Self::Output {
+        self + &-rhs
+    }
+}
+
+impl<'a, 'b> ops::Mul<&'a Ratio> for &'b Ratio {
+    type Output = Ratio;
+    fn mul(self, rhs: &'a Ratio) -> Self::Output {
+        debug_assert!(self.test_invariant() && rhs.test_invariant());
+        Ratio::new(&self.numer * &rhs.numer, &self.denom * &rhs.denom)
+    }
+}
+
+impl<'a, 'b> ops::Div<&'a Ratio> for &'b Ratio {
+    type Output = Ratio;
+    fn div(self, rhs: &'a Ratio) -> Self::Output {
+        debug_assert!(self.test_invariant() && rhs.test_invariant());
+        if rhs.numer.magnitude() == &BigInt::new(0) {
+            panic!("Division of Ratio by zero");
+        }
+        // Multiply by the reciprocal, moving the sign of `rhs` into the numerator.
+        let numer = &self.numer * &SignedBigInt::from_parts(rhs.numer.is_negative(), rhs.denom.clone());
+        Ratio::new(numer, &self.denom * rhs.numer.magnitude())
+    }
+}
+
+// The remaining combinations of owned and borrowed operands all forward to the borrowed one above.
+macro_rules! forward_binop {
+    ($trait_:ident, $method:ident) => {
+        impl<'a> ops::$trait_<Ratio> for &'a Ratio {
+            type Output = Ratio;
+            #[inline]
+            fn $method(self, rhs: Ratio) -> Self::Output {
+                ops::$trait_::$method(self, &rhs)
+            }
+        }
+
+        impl<'a> ops::$trait_<&'a Ratio> for Ratio {
+            type Output = Ratio;
+            #[inline]
+            fn $method(self, rhs: &'a Ratio) -> Self::Output {
+                ops::$trait_::$method(&self, rhs)
+            }
+        }
+
+        impl ops::$trait_<Ratio> for Ratio {
+            type Output = Ratio;
+            #[inline]
+            fn $method(self, rhs: Ratio) -> Self::Output {
+                ops::$trait_::$method(&self, &rhs)
+            }
+        }
+    }
+}
+
+forward_binop!(Add, add);
+forward_binop!(Sub, sub);
+forward_binop!(Mul, mul);
+forward_binop!(Div, div);
+
+#[cfg(test)]
+mod tests {
+    use bigint::BigInt;
+    use signed_bigint::SignedBigInt;
+    use super::{Ratio, ParseRatioError};
+
+    fn r(numer: i64, denom: u64) -> Ratio {
+        Ratio::new(SignedBigInt::from(numer), BigInt::new(denom))
+    }
+
+    fn p(s: &str) -> Ratio {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_reduced() {
+        let x = r(-6, 8);
+        assert!(x.test_invariant());
+        assert_eq!(x.numer(), &SignedBigInt::from(-3i64));
+        assert_eq!(x.denom(), &BigInt::new(4));
+        assert_eq!(r(0, 5), Ratio::from(0));
+        assert!(r(10, 5).is_integer());
+    }
+
+    #[test]
+    #[should_panic(expected = "Ratio with denominator zero")]
+    fn test_zero_denom() {
+        r(1, 0);
+    }
+
+    #[test]
+    fn test_ops() {
+        assert_eq!(r(1, 2) + r(1, 3), r(5, 6));
+        assert_eq!(r(1, 2) - r(3, 4), r(-1, 4));
+        assert_eq!(r(-2, 3) * r(9, 4), r(-3, 2));
+        assert_eq!(r(1, 2) / r(-3, 4), r(-2, 3));
+        assert_eq!(r(-1, 2) / r(-1, 4), Ratio::from(2));
+        assert_eq!(r(1, 3) - r(1, 3), Ratio::from(0));
+        // Tenths add up exactly, unlike with floats.
+        let tenth = p("0.1");
+        assert_eq!(&(&tenth + &tenth) + &tenth, p("0.3"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Division of Ratio by zero")]
+    fn test_div_zero() {
+        let _ = r(1, 2) / Ratio::from(0);
+    }
+
+    #[test]
+    fn test_ord() {
+        assert!(r(1, 3) < r(1, 2));
+        assert!(r(-1, 2) < r(-1, 3));
+        assert!(r(-1, 2) < r(1, 1000));
+        assert_eq!(r(2, 4).cmp(&r(1, 2)), ::std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_display_parse() {
+        assert_eq!(r(-6, 8).to_string(), "-3/4");
+        assert_eq!(Ratio::from(7).to_string(), "7/1");
+        assert_eq!(format!("{:>6}", r(1, 2)), "   1/2");
+        assert_eq!(p("3/4"), r(3, 4));
+        assert_eq!(p("-6/8"), r(-3, 4));
+        assert_eq!(p("+12"), Ratio::from(12));
+        assert_eq!(p("1.25"), r(5, 4));
+        assert_eq!(p("-0.050"), r(-1, 20));
+        assert_eq!(p(".5"), r(1, 2));
+        assert_eq!(p("2."), Ratio::from(2));
+        assert_eq!(p("1.2_5"), r(5, 4));
+        assert_eq!(p("1.5_"), r(3, 2));
+        assert_eq!(p("1_000.0_1"), r(100001, 100));
+        assert_eq!(p(&r(-22, 7).to_string()), r(-22, 7));
+        assert_eq!("3/0".parse::<Ratio>(), Err(ParseRatioError::ZeroDenominator));
+        assert_eq!("3/".parse::<Ratio>(), Err(ParseRatioError::Empty));
+        assert_eq!(".".parse::<Ratio>(), Err(ParseRatioError::Empty));
+        assert_eq!("3/-4".parse::<Ratio>(), Err(ParseRatioError::InvalidDigit { position: 2, found: '-' }));
+        assert_eq!("-1.2x".parse::<Ratio>(), Err(ParseRatioError::InvalidDigit { position: 4, found: 'x' }));
+        assert_eq!("1.2.3".parse::<Ratio>(), Err(ParseRatioError::InvalidDigit { position: 3, found: '.' }));
+        assert_eq!("1._".parse::<Ratio>(), Err(ParseRatioError::InvalidDigit { position: 2, found: '_' }));
+        assert_eq!("1.__5".parse::<Ratio>(), Err(ParseRatioError::InvalidDigit { position: 2, found: '_' }));
+    }
+
+    #[test]
+    fn test_round() {
+        assert_eq!(r(2, 3).round(2), r(67, 100));
+        assert_eq!(r(-2, 3).round(2), r(-67, 100));
+        assert_eq!(r(1, 8).round(2), r(13, 100));
+        assert_eq!(r(-1, 8).round(2), r(-13, 100));
+        assert_eq!(r(1, 3).round(0), Ratio::from(0));
+        assert!(r(-1, 3).round(1).numer().is_negative());
+        assert_eq!(r(2, 3).to_decimal_string(4), "0.6667");
+        assert_eq!(r(-1, 200).to_decimal_string(2), "-0.01");
+        assert_eq!(r(-1, 300).to_decimal_string(2), "0.00");
+        assert_eq!(r(5, 2).to_decimal_string(0), "3");
+        assert_eq!(Ratio::from(12).to_decimal_string(3), "12.000");
+    }
+}
@@ -1,7 +1,11 @@
 use std::io::prelude::*;
-use std::{io, fs, thread, process, cmp};
+use std::{io, fs, fmt, thread, process, cmp, mem};
 use std::sync::mpsc::{sync_channel, SyncSender, Receiver};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use regex::{self, Regex};
+use glob::Glob;
 
 #[derive(Clone,Copy)]
 enum OutputMode {
@@ -12,15 +16,22 @@ enum OutputMode {
 use self::OutputMode::*;
 
 struct Options {
-    files: Vec<String>,
+    files: Vec<PathBuf>,
     pattern: String,
+    regex: Option<Regex>, // if this is `None`, we search for `pattern` literally
     output_mode: OutputMode,
+    before: usize, // the number of context lines to print before a match
+    after: usize, // the number of context lines to print after a match
+    jobs: usize, // the number of threads that do the matching
+    unordered: bool, // whether the lines may be printed in the order the threads are done with them
+    walk_failed: bool, // whether there were errors while collecting the files in directories
 }
 
 struct Line {
     data: String,
     file: usize,
     line: usize,
+    is_match: bool, // if this is not set, the line is only there as context for a match
 }
 
 impl PartialEq for Line {
@@ -34,21 +45,172 @@ impl PartialOrd for Line {
     }
 }
 
-fn read_files(options: Arc<Options>, out_channel: SyncSender<Line>) {
-    for (fileidx, file) in options.files.iter().enumerate() {
-        let file = fs::File::open(file).unwrap();
-        let file = io::BufReader::new(file);
-        for (lineidx, line) in file.lines().enumerate() {
-            let line = Line { data: line.unwrap(), file: fileidx, line: lineidx };
-            out_channel.send(line).unwrap();
+// Print an error about `path` in the format of grep.
+fn report_error<E: fmt::Display>(path: &Path, err: E) {
+    eprintln!("rgrep: {}: {}", path.display(), err);
+}
+
+// The number of lines the reader puts into one batch. Sending the lines in batches keeps the cost of the channels
+// low compared to the matching, which is what makes it worth spreading the matching over several threads.
+const BATCH_LINES: usize = 256;
+
+// Read the files line by line, and send the lines on in batches. Returns whether there were any errors.
+// A file that cannot be read is reported, and we continue with the next one. A line that is not valid UTF-8 is
+// reported and skipped, and we continue with the next line.
+fn read_files(options: Arc<Options>, out_channel: SyncSender<Vec<Line>>) -> bool {
+    let mut failed = false;
+    let mut batch = Vec::with_capacity(BATCH_LINES);
+    for (fileidx, path) in options.files.iter().enumerate() {
+        let mut file = match fs::File::open(path) {
+            Ok(file) => io::BufReader::new(file),
+            Err(err) => {
+                report_error(path, err);
+                failed = true;
+                continue;
+            }
+        };
+        // We do not use `lines`, so that we are the ones to decide how to go on after a line that is not UTF-8.
+        for lineidx in 0.. {
+            let mut data = Vec::new();
+            match file.read_until(b'\n', &mut data) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(err) => {
+                    report_error(path, err);
+                    failed = true;
+                    break;
+                }
+            }
+            // Like `lines`, we strip the line ending, be it `\n` or `\r\n`.
+            if data.last() == Some(&b'\n') {
+                data.pop();
+                if data.last() == Some(&b'\r') {
+                    data.pop();
+                }
+            }
+            match String::from_utf8(data) {
+                Ok(data) => batch.push(Line { data, file: fileidx, line: lineidx, is_match: false }),
+                Err(_) => {
+                    report_error(path, format_args!("line {} is not valid UTF-8, skipping it", lineidx));
+                    failed = true;
+                    continue;
+                }
+            }
+            if batch.len() == BATCH_LINES {
+                let full = mem::replace(&mut batch, Vec::with_capacity(BATCH_LINES));
+                // If the receiving end is gone, there is no point in reading any further.
+                if out_channel.send(full).is_err() {
+                    return failed;
+                }
+            }
+        }
+    }
+    if !batch.is_empty() {
+        let _ = out_channel.send(batch);
+    }
+    failed
+}
+
+// Decide which lines of the batches match. There are `options.jobs` of these running at the same time, taking turns
+// to get the next batch from `in_channel`. Since the batches arrive in order, every worker sends them on in order.
+fn match_lines(options: Arc<Options>, in_channel: Arc<Mutex<Receiver<Vec<Line>>>>,
+               out_channel: SyncSender<Vec<Line>>) {
+    // The cache of a regular expression is behind a lock, so every worker gets its own.
+    let regex = options.regex.clone();
+    loop {
+        // The lock is only held while waiting for a batch, so that the others can get theirs while we are matching.
+        let mut batch = match in_channel.lock().unwrap().recv() {
+            Ok(batch) => batch,
+            Err(_) => return,
+        };
+        for line in batch.iter_mut() {
+            line.is_match = match regex {
+                Some(ref regex) => regex.is_match(&line.data),
+                None => line.data.contains(&options.pattern),
+            };
+        }
+        if out_channel.send(batch).is_err() {
+            return;
+        }
+    }
+}
+
+// Merges the batches coming from the workers back into the order of the files and lines. As the batches from every
+// single worker are already in order, we only ever need to look at the next batch of each of them.
+struct OrderedBatches {
+    channels: Vec<Receiver<Vec<Line>>>,
+    next: Vec<Option<Vec<Line>>>,
+}
+
+impl OrderedBatches {
+    fn new(channels: Vec<Receiver<Vec<Line>>>) -> OrderedBatches {
+        let next = channels.iter().map(|_| None).collect();
+        OrderedBatches { channels, next }
+    }
+}
+
+impl Iterator for OrderedBatches {
+    type Item = Vec<Line>;
+
+    fn next(&mut self) -> Option<Vec<Line>> {
+        // Every worker that is still running either sends us another batch, or finishes. Until we know which, we
+        // cannot tell whether it has the batch that comes next. Batches are never empty.
+        for (channel, next) in self.channels.iter().zip(self.next.iter_mut()) {
+            if next.is_none() {
+                *next = channel.recv().ok();
+            }
         }
+        let (idx, _) = self.next.iter().enumerate()
+            .filter_map(|(idx, batch)| batch.as_ref().map(|batch| (idx, (batch[0].file, batch[0].line))))
+            .min_by_key(|&(_, position)| position)?;
+        self.next[idx].take()
+    }
+}
+
+// Send on the matching lines that come in from the workers, together with the context lines around them. Unless
+// there is only a single channel, the batches are first brought back in order. We only ever hold on to the last
+// `options.before` lines, so that this works without reading entire files.
+fn filter_lines(options: Arc<Options>, mut in_channels: Vec<Receiver<Vec<Line>>>, out_channel: SyncSender<Line>) {
+    if in_channels.len() == 1 {
+        let in_channel = in_channels.pop().unwrap();
+        select_lines(&options, in_channel.iter().flatten(), &out_channel);
+    } else {
+        select_lines(&options, OrderedBatches::new(in_channels).flatten(), &out_channel);
     }
 }
 
-fn filter_lines(options: Arc<Options>, in_channel: Receiver<Line>, out_channel: SyncSender<Line>) {
-    for line in in_channel.iter() {
-        if line.data.contains(&options.pattern) {
-            out_channel.send(line).unwrap();
+fn select_lines<I: Iterator<Item=Line>>(options: &Options, lines: I, out_channel: &SyncSender<Line>) {
+    let mut before: VecDeque<Line> = VecDeque::with_capacity(options.before);
+    let mut after_left = 0; // the number of lines still to be sent after the last match
+    let mut current_file = None;
+    for line in lines {
+        // Context does not extend across files.
+        if current_file != Some(line.file) {
+            current_file = Some(line.file);
+            before.clear();
+            after_left = 0;
+        }
+        if line.is_match {
+            after_left = options.after;
+            for context in before.drain(..) {
+                if out_channel.send(context).is_err() {
+                    return;
+                }
+            }
+        } else if after_left > 0 {
+            after_left -= 1;
+        } else {
+            // This line is not needed now, but it may become context for a match further down.
+            if options.before > 0 {
+                if before.len() == options.before {
+                    before.pop_front();
+                }
+                before.push_back(line);
+            }
+            continue;
+        }
+        if out_channel.send(line).is_err() {
+            return;
         }
     }
 }
@@ -80,75 +242,648 @@ fn sort<T: PartialOrd>(data: &mut [T]) {
     sort(part2);
 }
 
-fn output_lines(options: Arc<Options>, in_channel: Receiver<Line>) {
+// Print the lines to `out`, and return whether there were any. We stop early if `out` is closed, e.g. by a pipe to
+// `head`.
+fn output_lines<W: Write>(options: Arc<Options>, in_channel: Receiver<Line>, mut out: W) -> bool {
     match options.output_mode {
         Print => {
+            let mut found = false;
+            let mut last = None; // the file and line index of the line we printed last
             for line in in_channel.iter() {
-                println!("{}:{}: {}", options.files[line.file], line.line, line.data);
+                found |= line.is_match;
+                // Like grep, we separate groups of lines that are not adjacent, if we print context at all.
+                let adjacent = last == Some((line.file, line.line.wrapping_sub(1)));
+                if (options.before > 0 || options.after > 0) && last.is_some() && !adjacent &&
+                    writeln!(out, "--").is_err() {
+                    break;
+                }
+                last = Some((line.file, line.line));
+                // Matches are marked by a `:`, context lines by a `-`.
+                let marker = if line.is_match { ':' } else { '-' };
+                if writeln!(out, "{}{}{}{} {}", options.files[line.file].display(), marker, line.line, marker,
+                            line.data).is_err() {
+                    break;
+                }
             }
+            found
         },
         Count => {
             let count = in_channel.iter().count();
-            println!("{} hits for {}.", count, options.pattern);
+            let _ = writeln!(out, "{} hits for {}.", count, options.pattern);
+            count > 0
         },
         SortAndPrint => {
             let mut data: Vec<Line> = in_channel.iter().collect();
             sort(&mut data[..]);
             for line in data.iter() {
-                println!("{}:{}: {}", options.files[line.file], line.line, line.data);
+                if writeln!(out, "{}:{}: {}", options.files[line.file].display(), line.line, line.data).is_err() {
+                    break;
+                }
             }
+            !data.is_empty()
         }
     }
 }
 
+// The settings for walking directories with `-r`.
+struct WalkOptions {
+    hidden: bool,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+}
+
+impl WalkOptions {
+    // Decide whether the file with the given name is to be searched, based on `--include` and `--exclude`.
+    fn selects(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.is_match(name))) &&
+            !self.exclude.iter().any(|glob| glob.is_match(name))
+    }
+}
+
+// One line of a `.gitignore` or `.ignore` file.
+struct IgnoreRule {
+    glob: Glob,
+    negated: bool, // a rule starting with `!` re-includes what an earlier rule ignored
+    dir_only: bool, // a rule ending in `/` only applies to directories
+    anchored: bool, // a rule with a `/` is matched against the whole path, rather than just the name
+}
+
+impl IgnoreRule {
+    fn parse(line: &str) -> Option<IgnoreRule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        if line.is_empty() {
+            return None;
+        }
+        Some(IgnoreRule { glob: Glob::new(line), negated, dir_only, anchored })
+    }
+}
+
+// The rules of an ignore file, which apply to everything below the directory the file is in. The rules are matched
+// against paths relative to that directory. For an ignore file above the directory we search, `dir` is the directory we
+// search, and `prefix` is how to get there from the ignore file.
+struct IgnoreFile {
+    dir: PathBuf,
+    prefix: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreFile {
+    fn load(dir: &Path, name: &str) -> Option<IgnoreFile> {
+        let contents = fs::read_to_string(dir.join(name)).ok()?;
+        let rules = contents.lines().filter_map(IgnoreRule::parse).collect();
+        Some(IgnoreFile { dir: dir.to_path_buf(), prefix: PathBuf::new(), rules })
+    }
+}
+
+// Load the ignore files in the directories above `root`, up to the top of the git repository `root` is in. Like git,
+// we do not look at any of them if `root` is not in a repository at all. The files further up come first.
+fn parent_ignore_files(root: &Path) -> Vec<IgnoreFile> {
+    let absolute = match fs::canonicalize(root) {
+        Ok(absolute) => absolute,
+        Err(_) => return Vec::new(),
+    };
+    let mut levels = Vec::new();
+    let mut current = absolute.as_path();
+    let mut prefix = PathBuf::new();
+    while !current.join(".git").exists() {
+        let (parent, name) = match (current.parent(), current.file_name()) {
+            (Some(parent), Some(name)) => (parent, name),
+            _ => return Vec::new(),
+        };
+        prefix = Path::new(name).join(&prefix);
+        let mut level = Vec::new();
+        for name in &[".gitignore", ".ignore"] {
+            if let Some(mut ignore_file) = IgnoreFile::load(parent, name) {
+                ignore_file.dir = root.to_path_buf();
+                ignore_file.prefix = prefix.clone();
+                level.push(ignore_file);
+            }
+        }
+        levels.push(level);
+        current = parent;
+    }
+    levels.into_iter().rev().flatten().collect()
+}
+
+// Decide whether `path` is ignored. Later rules take precedence over earlier ones, and rules from files further
+// down in the tree take precedence over rules from further up.
+fn is_ignored(ignore_files: &[IgnoreFile], path: &Path, is_dir: bool) -> bool {
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let mut ignored = false;
+    for ignore_file in ignore_files {
+        let relative = match path.strip_prefix(&ignore_file.dir) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        // Globs always use `/` as separator.
+        let relative: Vec<_> = ignore_file.prefix.iter().chain(relative.iter())
+            .map(|component| component.to_string_lossy()).collect();
+        let relative = relative.join("/");
+        for rule in ignore_file.rules.iter().filter(|rule| is_dir || !rule.dir_only) {
+            let target = if rule.anchored { &relative[..] } else { &name[..] };
+            if rule.glob.is_match(target) {
+                ignored = !rule.negated;
+            }
+        }
+    }
+    ignored
+}
+
+// Collect the files to search in `dir` and its subdirectories, in alphabetical order. Returns whether there were
+// any errors; the directories we cannot read are reported and skipped.
+fn walk(dir: &Path, options: &WalkOptions, ignore_files: &mut Vec<IgnoreFile>, files: &mut Vec<PathBuf>) -> bool {
+    let mut failed = false;
+    let ignore_files_len = ignore_files.len();
+    // `.ignore` comes second, so that it can override `.gitignore`.
+    ignore_files.extend(IgnoreFile::load(dir, ".gitignore"));
+    ignore_files.extend(IgnoreFile::load(dir, ".ignore"));
+
+    let mut entries = Vec::new();
+    match fs::read_dir(dir) {
+        Ok(dir_entries) => {
+            for entry in dir_entries {
+                match entry {
+                    Ok(entry) => entries.push(entry),
+                    Err(err) => {
+                        report_error(dir, err);
+                        failed = true;
+                    }
+                }
+            }
+        }
+        Err(err) => {
+            report_error(dir, err);
+            failed = true;
+        }
+    }
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !options.hidden && name.starts_with('.') {
+            continue;
+        }
+        // We follow symbolic links to files, but not to directories, so that we cannot end up going in circles.
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(err) => {
+                report_error(&path, err);
+                failed = true;
+                continue;
+            }
+        };
+        let is_dir = file_type.is_dir();
+        let is_file = file_type.is_file() ||
+            (file_type.is_symlink() && fs::metadata(&path).map(|metadata| metadata.is_file()).unwrap_or(false));
+        if !(is_dir || is_file) || is_ignored(ignore_files, &path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            failed |= walk(&path, options, ignore_files, files);
+        } else if options.selects(&name) {
+            files.push(path);
+        }
+    }
+    ignore_files.truncate(ignore_files_len);
+    failed
+}
+
 static USAGE: &'static str = "
-Usage: rgrep [-c] [-s] <pattern> <file>...
+Usage: rgrep [options] [--include=<glob>]... [--exclude=<glob>]... <pattern> <file>...
 
 Options:
-    -c, --count  Count number of matching lines (rather than printing them).
-    -s, --sort   Sort the lines before printing.
+    -c, --count         Count number of matching lines (rather than printing them).
+    -s, --sort          Sort the lines before printing.
+    -e, --regexp        Interpret the pattern as a regular expression.
+    -i, --ignore-case   Ignore the difference between upper and lower case.
+    -r, --recursive     Search the files in directories, and their subdirectories. Files and directories listed
+                        in .gitignore and .ignore files are skipped, including those in the directories above,
+                        up to the top of the git repository.
+    --hidden            Also search hidden files and directories (whose names start with a dot) in directories.
+    --include=<glob>    Only search files whose name matches <glob> in directories.
+    --exclude=<glob>    Do not search files whose name matches <glob> in directories.
+    -A <num>, --after-context=<num>
+                        Print <num> lines of context after every matching line.
+    -B <num>, --before-context=<num>
+                        Print <num> lines of context before every matching line.
+    -C <num>, --context=<num>
+                        Print <num> lines of context before and after every matching line. Context lines are not
+                        printed together with '-c' or '-s'.
+    -j <num>, --jobs=<num>
+                        Use <num> threads to match the lines [default: 1].
+    --unordered         Print the lines as soon as they are matched, rather than in the order of the files and
+                        lines. This is faster with '-j', but cannot be combined with context lines.
 ";
 
 fn get_options() -> Options {
     use docopt::Docopt;
 
-    // Parse argv and exit the program with an error message if it fails.
-    let args = Docopt::new(USAGE).and_then(|d| d.parse()).unwrap_or_else(|e| e.exit());
+    // Parse argv and exit the program with an error message if it fails. Like grep, we use exit status 2 for errors.
+    let args = Docopt::new(USAGE).and_then(|d| d.parse()).unwrap_or_else(|e| {
+        if e.fatal() {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+        e.exit()
+    });
     let count = args.get_bool("-c");
     let sort = args.get_bool("-s");
+    let regexp = args.get_bool("-e");
+    let ignore_case = args.get_bool("-i");
+    let recursive = args.get_bool("-r");
     let pattern = args.get_str("<pattern>");
     let files = args.get_vec("<file>");
     if count && sort {
-        println!("Setting both '-c' and '-s' at the same time does not make any sense.");
-        process::exit(1);
+        eprintln!("rgrep: Setting both '-c' and '-s' at the same time does not make any sense.");
+        process::exit(2);
+    }
+
+    // `-A` and `-B` take precedence over `-C`.
+    let context_arg = |name: &str| -> Option<usize> {
+        let arg = args.get_str(name);
+        if arg.is_empty() {
+            return None;
+        }
+        match arg.parse() {
+            Ok(num) => Some(num),
+            Err(_) => {
+                eprintln!("rgrep: {}: invalid context length argument", arg);
+                process::exit(2);
+            }
+        }
+    };
+    let context = context_arg("-C").unwrap_or(0);
+    let (before, after) = if count || sort {
+        (0, 0)
+    } else {
+        (context_arg("-B").unwrap_or(context), context_arg("-A").unwrap_or(context))
+    };
+
+    let jobs = match args.get_str("-j").parse() {
+        Ok(jobs) if jobs > 0 => jobs,
+        _ => {
+            eprintln!("rgrep: {}: invalid number of jobs", args.get_str("-j"));
+            process::exit(2);
+        }
+    };
+    let unordered = args.get_bool("--unordered");
+    if unordered && (before > 0 || after > 0) {
+        eprintln!("rgrep: Context lines cannot be printed with '--unordered'.");
+        process::exit(2);
+    }
+
+    // Case-insensitive search for a plain pattern is done by a regular expression that matches it literally.
+    let regex = if regexp || ignore_case {
+        let pattern = if regexp { pattern.to_string() } else { regex::escape(pattern) };
+        let regex = if ignore_case { Regex::new_ignore_case(&pattern) } else { Regex::new(&pattern) };
+        match regex {
+            Ok(regex) => Some(regex),
+            Err(err) => {
+                eprintln!("rgrep: Invalid regular expression '{}': {}.", pattern, err);
+                process::exit(2);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Files given explicitly are always searched. Only the files we find in directories are filtered.
+    let walk_options = WalkOptions {
+        hidden: args.get_bool("--hidden"),
+        include: args.get_vec("--include").iter().map(|glob| Glob::new(glob)).collect(),
+        exclude: args.get_vec("--exclude").iter().map(|glob| Glob::new(glob)).collect(),
+    };
+    let mut paths = Vec::new();
+    let mut walk_failed = false;
+    for file in files {
+        let file = PathBuf::from(file);
+        if recursive && file.is_dir() {
+            walk_failed |= walk(&file, &walk_options, &mut parent_ignore_files(&file), &mut paths);
+        } else {
+            paths.push(file);
+        }
     }
 
     // We need to make the strings owned to construct the `Options` instance.
     Options {
-        files: files.iter().map(|file| file.to_string()).collect(),
+        files: paths,
         pattern: pattern.to_string(),
+        regex,
         output_mode: if count { Count } else if sort { SortAndPrint } else { Print },
+        before,
+        after,
+        jobs,
+        unordered,
+        walk_failed,
     }
 }
 
-fn run(options: Options) {
-    let options = Arc::new(options);
-
+// Search the files, and send the lines to be printed to `out_channel`. Returns whether there were errors reading.
+fn search(options: Arc<Options>, out_channel: SyncSender<Line>) -> bool {
     // This sets up the chain of threads. Use `sync_channel` with buffer-size of 16 to avoid needlessly filling RAM.
+    // The matching is spread over `options.jobs` threads. Each of them gets its own channel to `filter_lines`, so that
+    // the order can be restored there, unless we do not care about the order.
     let (line_sender, line_receiver) = sync_channel(16);
-    let (filtered_sender, filtered_receiver) = sync_channel(16);
+    let line_receiver = Arc::new(Mutex::new(line_receiver));
+    let mut matched_receivers = Vec::new();
+    let mut shared_sender = None;
 
     let options1 = options.clone();
-    let handle1 = thread::spawn(move || read_files(options1, line_sender));
-    let options2 = options.clone();
-    let handle2 = thread::spawn(move || filter_lines(options2, line_receiver, filtered_sender));
-    let options3 = options.clone();
-    let handle3 = thread::spawn(move || output_lines(options3, filtered_receiver));
-    handle1.join().unwrap();
-    handle2.join().unwrap();
-    handle3.join().unwrap();
+    let reader = thread::spawn(move || read_files(options1, line_sender));
+    let mut workers = Vec::new();
+    for _ in 0..options.jobs {
+        let matched_sender = if options.unordered {
+            shared_sender.get_or_insert_with(|| {
+                let (sender, receiver) = sync_channel(16);
+                matched_receivers.push(receiver);
+                sender
+            }).clone()
+        } else {
+            let (sender, receiver) = sync_channel(16);
+            matched_receivers.push(receiver);
+            sender
+        };
+        let options2 = options.clone();
+        let line_receiver = line_receiver.clone();
+        workers.push(thread::spawn(move || match_lines(options2, line_receiver, matched_sender)));
+    }
+    // The channels are only closed once all the workers are done with their copies of the ends. If we held on to
+    // the receiving end of the lines, the reader would never learn that the workers stopped early.
+    drop(shared_sender);
+    drop(line_receiver);
+    filter_lines(options, matched_receivers, out_channel);
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    reader.join().unwrap()
+}
+
+// Run the search, and return the exit status: 0 if we found something, 1 if we did not, and 2 if there was an error.
+fn run<W: Write + Send + 'static>(options: Options, out: W) -> i32 {
+    let options = Arc::new(options);
+    let (filtered_sender, filtered_receiver) = sync_channel(16);
+    let options1 = options.clone();
+    let output = thread::spawn(move || output_lines(options1, filtered_receiver, out));
+    let read_failed = search(options.clone(), filtered_sender);
+    let found = output.join().unwrap();
+    if read_failed || options.walk_failed {
+        2
+    } else if found {
+        0
+    } else {
+        1
+    }
 }
 
 pub fn main() {
-    run(get_options());
+    let status = run(get_options(), io::stdout());
+    process::exit(status);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::sync::mpsc::sync_channel;
+    use std::{env, fs, io, process, thread};
+    use regex::Regex;
+    use rng::XorShiftRng;
+    use super::{IgnoreFile, IgnoreRule, Line, Options, OutputMode, WalkOptions, is_ignored, parent_ignore_files, run,
+                search, walk};
+
+    fn ignore_file(dir: &str, lines: &str) -> IgnoreFile {
+        let rules = lines.lines().filter_map(IgnoreRule::parse).collect();
+        IgnoreFile { dir: PathBuf::from(dir), prefix: PathBuf::new(), rules }
+    }
+
+    #[test]
+    fn test_ignore_rules() {
+        let files = vec![
+            ignore_file("root", "# comment\n\n*.log\n!keep.log\ntarget/\n/docs/*.md\nbuild/out\n"),
+            ignore_file("root/sub", "*.txt\n!important.log\n"),
+        ];
+        let ignored = |path: &str, is_dir: bool| is_ignored(&files, Path::new(path), is_dir);
+        assert!(ignored("root/x.log", false) && ignored("root/a/b/x.log", false));
+        assert!(!ignored("root/keep.log", false));
+        assert!(ignored("root/target", true) && !ignored("root/target", false));
+        assert!(ignored("root/docs/a.md", false) && !ignored("root/sub/docs/a.md", false));
+        assert!(ignored("root/build/out", false) && !ignored("root/x/build/out", false));
+        assert!(ignored("root/sub/a.txt", false) && !ignored("root/a.txt", false));
+        assert!(!ignored("root/sub/important.log", false) && ignored("root/sub/other.log", false));
+        assert!(!ignored("root/# comment", false));
+    }
+
+    #[test]
+    fn test_parent_ignore_files() {
+        let root = env::temp_dir().join(format!("rgrep-parent-{}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for dir in &[".git", "sub/skip", "sub/keep"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join(".gitignore"), "*.log\nskip/\n/sub/keep/b.rs\n").unwrap();
+        fs::write(root.join("sub/.ignore"), "!keep.log\n").unwrap();
+        for file in &["sub/a.rs", "sub/a.log", "sub/keep.log", "sub/skip/c.rs", "sub/keep/b.rs", "sub/keep/c.rs"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let walk_in = |dir: &str| {
+            let options = WalkOptions { hidden: false, include: Vec::new(), exclude: Vec::new() };
+            let dir = root.join(dir);
+            let mut files = Vec::new();
+            assert!(!walk(&dir, &options, &mut parent_ignore_files(&dir), &mut files));
+            files.iter().map(|file| file.strip_prefix(&root).unwrap().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(walk_in("sub"), vec!["sub/a.rs", "sub/keep/c.rs", "sub/keep.log"]);
+        assert_eq!(walk_in("sub/keep"), vec!["sub/keep/c.rs"]);
+        // Outside of a repository, the ignore files further up do not count.
+        fs::remove_dir(root.join(".git")).unwrap();
+        assert_eq!(walk_in("sub/keep"), vec!["sub/keep/b.rs", "sub/keep/c.rs"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    // Write the files to a fresh directory below the temporary directory, and return their paths.
+    fn write_files(name: &str, files: &[String]) -> Vec<PathBuf> {
+        let dir = env::temp_dir().join(format!("rgrep-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        files.iter().enumerate().map(|(idx, data)| {
+            let path = dir.join(format!("{:03}", idx));
+            fs::write(&path, data).unwrap();
+            path
+        }).collect()
+    }
+
+    fn options(files: Vec<PathBuf>, pattern: &str, before: usize, after: usize, jobs: usize,
+               unordered: bool) -> Options {
+        Options {
+            files,
+            pattern: pattern.to_string(),
+            regex: Regex::new(pattern).ok(),
+            output_mode: OutputMode::Print,
+            before,
+            after,
+            jobs,
+            unordered,
+            walk_failed: false,
+        }
+    }
+
+    // Run `search`, and return whether there were errors, and the lines that come out, with their marker.
+    fn search_lines(options: Options) -> (bool, Vec<(usize, usize, char)>) {
+        let (filtered_sender, filtered_receiver) = sync_channel(16);
+        let output = thread::spawn(move || {
+            filtered_receiver.iter()
+                .map(|line: Line| (line.file, line.line, if line.is_match { ':' } else { '-' })).collect()
+        });
+        let failed = search(Arc::new(options), filtered_sender);
+        (failed, output.join().unwrap())
+    }
+
+    fn run_search(options: Options) -> Vec<(usize, usize, char)> {
+        let (failed, lines) = search_lines(options);
+        assert!(!failed);
+        lines
+    }
+
+    // Search the files, which have one line per character, for `x`.
+    fn filter(files: &[&str], before: usize, after: usize) -> Vec<(usize, usize, char)> {
+        let files: Vec<String> = files.iter().map(|data| data.chars().map(|c| format!("{}\n", c)).collect()).collect();
+        let paths = write_files(&format!("context-{}-{}", before, after), &files);
+        let result = run_search(options(paths.clone(), "x", before, after, 1, false));
+        fs::remove_dir_all(paths[0].parent().unwrap()).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_context() {
+        assert_eq!(filter(&["..x...x..", "x."], 0, 0), vec![(0, 2, ':'), (0, 6, ':'), (1, 0, ':')]);
+        assert_eq!(filter(&["..x...x.."], 1, 2),
+                   vec![(0, 1, '-'), (0, 2, ':'), (0, 3, '-'), (0, 4, '-'), (0, 5, '-'), (0, 6, ':'), (0, 7, '-'),
+                        (0, 8, '-')]);
+        // Overlapping context is only sent once, and context does not cross file boundaries.
+        assert_eq!(filter(&["xx.", ".x"], 3, 0), vec![(0, 0, ':'), (0, 1, ':'), (1, 0, '-'), (1, 1, ':')]);
+        assert_eq!(filter(&["x", "..x"], 0, 5), vec![(0, 0, ':'), (1, 2, ':')]);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let mut paths = write_files("errors", &["x0\nx1\n".to_string(), String::new()]);
+        fs::write(&paths[1], &b"x0\n\xffx\r\nx2\r\n"[..]).unwrap();
+        paths.insert(0, paths[0].with_file_name("missing"));
+        // The missing file and the broken line are skipped, but everything else is still searched.
+        let (failed, lines) = search_lines(options(paths.clone(), "x", 0, 0, 1, false));
+        assert!(failed);
+        assert_eq!(lines, vec![(1, 0, ':'), (1, 1, ':'), (2, 0, ':'), (2, 2, ':')]);
+        // The line endings are not part of the lines.
+        assert_eq!(search_lines(options(paths.clone(), "x2$", 0, 0, 1, false)), (true, vec![(2, 2, ':')]));
+        fs::remove_dir_all(paths[1].parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_exit_status() {
+        let paths = write_files("status", &["a\nb\n".to_string()]);
+        let status = |pattern: &str, paths: &[PathBuf], walk_failed: bool| {
+            let mut options = options(paths.to_vec(), pattern, 0, 0, 2, false);
+            options.walk_failed = walk_failed;
+            run(options, io::sink())
+        };
+        assert_eq!(status("b", &paths, false), 0);
+        assert_eq!(status("c", &paths, false), 1);
+        assert_eq!(status("b", &paths, true), 2);
+        let with_missing = vec![paths[0].clone(), paths[0].with_file_name("missing")];
+        assert_eq!(status("b", &with_missing, false), 2);
+        assert_eq!(status("c", &with_missing, false), 2);
+        fs::remove_dir_all(paths[0].parent().unwrap()).unwrap();
+    }
+
+    // A writer like a pipe whose reading end was closed.
+    struct ClosedPipe;
+
+    impl io::Write for ClosedPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_closed_output() {
+        // There are many more lines than fit into the channels, so everybody has to notice that output stopped.
+        let paths = write_files("closed", &["x\n".repeat(100_000)]);
+        for &jobs in &[1, 3] {
+            assert_eq!(run(options(paths.clone(), "x", 0, 0, jobs, false), ClosedPipe), 0);
+        }
+        fs::remove_dir_all(paths[0].parent().unwrap()).unwrap();
+    }
+
+    // Generate `count` files with random lines of words, of very different lengths (including empty ones).
+    fn corpus(count: usize, max_lines: u64, seed: u64) -> Vec<String> {
+        const WORDS: [&str; 8] = ["foo", "bar", "baz", "quux", "rust", "thread", "channel", "x42"];
+        let mut rng = XorShiftRng::new(seed);
+        (0..count).map(|_| {
+            let lines = rng.next_u64() % max_lines;
+            let mut data = String::new();
+            for _ in 0..lines {
+                for _ in 0..rng.next_u64() % 12 {
+                    data.push_str(WORDS[(rng.next_u64() % 8) as usize]);
+                    data.push(' ');
+                }
+                data.push('\n');
+            }
+            data
+        }).collect()
+    }
+
+    #[test]
+    fn test_jobs() {
+        let paths = write_files("jobs", &corpus(12, 3000, 2025));
+        let search_with = |before, after, jobs, unordered| {
+            run_search(options(paths.clone(), "foo ba[rz] x4", before, after, jobs, unordered))
+        };
+        let sequential = search_with(0, 0, 1, false);
+        assert!(sequential.len() > 100);
+        assert_eq!(search_with(0, 0, 4, false), sequential);
+        assert_eq!(search_with(2, 1, 3, false), search_with(2, 1, 1, false));
+        let mut unordered = search_with(0, 0, 4, true);
+        unordered.sort();
+        assert_eq!(unordered, sequential);
+        fs::remove_dir_all(paths[0].parent().unwrap()).unwrap();
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture` to see the timings.
+    #[test]
+    #[ignore]
+    fn bench_jobs() {
+        use std::time::Instant;
+
+        let files = corpus(64, 40_000, 4711);
+        let lines: usize = files.iter().map(|data| data.lines().count()).sum();
+        let paths = write_files("bench", &files);
+        let mut expected = None;
+        for &(jobs, unordered) in &[(1, false), (2, false), (4, false), (8, false), (4, true), (8, true)] {
+            let start = Instant::now();
+            let mut result = run_search(options(paths.clone(), "(foo|rust) [a-z]+ x4[0-9]", 0, 0, jobs, unordered));
+            println!("-j {}{}: {} of {} lines in {:?}", jobs, if unordered { " --unordered" } else { "" },
+                     result.len(), lines, start.elapsed());
+            result.sort();
+            assert_eq!(expected.get_or_insert_with(|| result.clone()), &result);
+        }
+        fs::remove_dir_all(paths[0].parent().unwrap()).unwrap();
+    }
 }
@@ -0,0 +1,46 @@
+/// A small pseudo-random number generator (xorshift64*). It is fully determined by its seed, which makes it a
+/// good fit for tests and randomized algorithms that need to be reproducible. It is *not* suited for cryptography!
+#[derive(Clone, Debug)]
+pub struct XorShiftRng {
+    state: u64, // never 0, or we would only ever produce 0
+}
+
+impl XorShiftRng {
+    /// Construct a generator from the given seed. Equal seeds produce equal sequences of numbers.
+    pub fn new(seed: u64) -> Self {
+        // Scramble the seed with one step of SplitMix64, so that similar seeds do not produce similar sequences.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        XorShiftRng { state: if z == 0 { 1 } else { z } }
+    }
+
+    /// Return the next number in the sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XorShiftRng;
+
+    #[test]
+    fn test_deterministic() {
+        let mut rng1 = XorShiftRng::new(42);
+        let mut rng2 = XorShiftRng::new(42);
+        let mut rng3 = XorShiftRng::new(43);
+        let v1: Vec<u64> = (0..100).map(|_| rng1.next_u64()).collect();
+        let v2: Vec<u64> = (0..100).map(|_| rng2.next_u64()).collect();
+        let v3: Vec<u64> = (0..100).map(|_| rng3.next_u64()).collect();
+        assert_eq!(v1, v2);
+        assert!(v1 != v3);
+        // The seed 0 must not get us stuck.
+        let mut rng = XorShiftRng::new(0);
+        assert!((0..10).any(|_| rng.next_u64() != 0));
+    }
+}
@@ -0,0 +1,337 @@
+use std::ops;
+use std::cmp;
+use std::fmt;
+use std::convert::TryFrom;
+use bigint::{BigInt, TryFromBigIntError};
+
+/// An arbitrary-precision integer that can also be negative: A sign, and the absolute value as `BigInt`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SignedBigInt {
+    negative: bool, // Zero is *never* negative, so that every number has exactly one representation.
+    magnitude: BigInt,
+}
+
+impl SignedBigInt {
+    /// Construct a number from its sign and absolute value.
+    pub fn from_parts(negative: bool, magnitude: BigInt) -> Self {
+        // take care of our invariant!
+        let negative = negative && magnitude != BigInt::new(0);
+        SignedBigInt { negative, magnitude }
+    }
+
+    fn test_invariant(&self) -> bool {
+        !self.negative || self.magnitude != BigInt::new(0)
+    }
+
+    /// Return whether the number is smaller than zero.
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Return the absolute value of the number.
+    pub fn magnitude(&self) -> &BigInt {
+        &self.magnitude
+    }
+
+    /// Divide by `other`, rounding towards zero. The remainder has the same sign as `self`, just like for the
+    /// primitive integer types. Panics if `other` is zero.
+    pub fn div_rem(&self, other: &SignedBigInt) -> (SignedBigInt, SignedBigInt) {
+        debug_assert!(self.test_invariant() && other.test_invariant());
+        let (quotient, remainder) = self.magnitude.div_rem(&other.magnitude);
+        (SignedBigInt::from_parts(self.negative != other.negative, quotient),
+         SignedBigInt::from_parts(self.negative, remainder))
+    }
+
+    /// Divide by `other` such that the remainder is never negative (Euclidean division). Panics if `other` is zero.
+    pub fn div_rem_euclid(&self, other: &SignedBigInt) -> (SignedBigInt, SignedBigInt) {
+        let (quotient, remainder) = self.div_rem(other);
+        if !remainder.negative {
+            return (quotient, remainder);
+        }
+        // Move the remainder up by `|other|`, and the quotient one step away from it.
+        let one = SignedBigInt::from(1i64);
+        let quotient = if other.negative { quotient + one } else { quotient - one };
+        let remainder = remainder + SignedBigInt::from(other.magnitude.clone());
+        (quotient, remainder)
+    }
+
+    /// The quotient of Euclidean division.
+    pub fn div_euclid(&self, other: &SignedBigInt) -> SignedBigInt {
+        self.div_rem_euclid(other).0
+    }
+
+    /// The remainder of Euclidean division, which is never negative.
+    pub fn rem_euclid(&self, other: &SignedBigInt) -> SignedBigInt {
+        self.div_rem_euclid(other).1
+    }
+}
+
+impl From<BigInt> for SignedBigInt {
+    fn from(magnitude: BigInt) -> Self {
+        SignedBigInt { negative: false, magnitude }
+    }
+}
+
+impl From<i128> for SignedBigInt {
+    fn from(x: i128) -> Self {
+        SignedBigInt::from_parts(x < 0, BigInt::from(x.unsigned_abs()))
+    }
+}
+
+impl From<i64> for SignedBigInt {
+    fn from(x: i64) -> Self {
+        SignedBigInt::from(x as i128)
+    }
+}
+
+/// Fails for negative numbers.
+impl TryFrom<SignedBigInt> for BigInt {
+    type Error = TryFromBigIntError;
+    fn try_from(x: SignedBigInt) -> Result<BigInt, TryFromBigIntError> {
+        if x.negative {
+            Err(TryFromBigIntError::new())
+        } else {
+            Ok(x.magnitude)
+        }
+    }
+}
+
+impl TryFrom<SignedBigInt> for i128 {
+    type Error = TryFromBigIntError;
+    fn try_from(x: SignedBigInt) -> Result<i128, TryFromBigIntError> {
+        let magnitude = u128::try_from(x.magnitude)?;
+        if x.negative {
+            // The range of negative numbers is one larger than the range of positive numbers.
+            if magnitude <= 1 << 127 {
+                Ok((magnitude as i128).wrapping_neg())
+            } else {
+                Err(TryFromBigIntError::new())
+            }
+        } else {
+            i128::try_from(magnitude).map_err(|_| TryFromBigIntError::new())
+        }
+    }
+}
+
+impl TryFrom<SignedBigInt> for i64 {
+    type Error = TryFromBigIntError;
+    fn try_from(x: SignedBigInt) -> Result<i64, TryFromBigIntError> {
+        let x = i128::try_from(x)?;
+        i64::try_from(x).map_err(|_| TryFromBigIntError::new())
+    }
+}
+
+impl Ord for SignedBigInt {
+    fn cmp(&self, other: &SignedBigInt) -> cmp::Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+            (false, true) => cmp::Ordering::Greater,
+            (true, false) => cmp::Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for SignedBigInt {
+    fn partial_cmp(&self, other: &SignedBigInt) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for SignedBigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(!self.negative, "", &self.magnitude.to_string())
+    }
+}
+
+impl<'a> ops::Neg for &'a SignedBigInt {
+    type Output = SignedBigInt;
+    fn neg(self) -> Self::Output {
+        SignedBigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl ops::Neg for SignedBigInt {
+    type Output = SignedBigInt;
+    fn neg(self) -> Self::Output {
+        SignedBigInt::from_parts(!self.negative, self.magnitude)
+    }
+}
+
+// Add `a` and `b`, where `b` is negated if `negate_b` is set.
+fn add_signed(a: &SignedBigInt, b: &SignedBigInt, negate_b: bool) -> SignedBigInt {
+    debug_assert!(a.test_invariant() && b.test_invariant());
+    let b_negative = b.negative != negate_b;
+    if a.negative == b_negative {
+        return SignedBigInt::from_parts(a.negative, &a.magnitude + &b.magnitude);
+    }
+    // The signs differ, so we subtract the smaller magnitude from the larger one.
+    if a.magnitude >= b.magnitude {
+        SignedBigInt::from_parts(a.negative, &a.magnitude - &b.magnitude)
+    } else {
+        SignedBigInt::from_parts(b_negative, &b.magnitude - &a.magnitude)
+    }
+}
+
+impl<'a, 'b> ops::Add<&'a SignedBigInt> for &'b SignedBigInt {
+    type Output = SignedBigInt;
+    fn add(self, rhs: &'a SignedBigInt) -> Self::Output {
+        add_signed(self, rhs, false)
+    }
+}
+
+impl<'a, 'b> ops::Sub<&'a SignedBigInt> for &'b SignedBigInt {
+    type Output = SignedBigInt;
+    fn sub(self, rhs: &'a SignedBigInt) -> Self::Output {
+        add_signed(self, rhs, true)
+    }
+}
+
+impl<'a, 'b> ops::Mul<&'a SignedBigInt> for &'b SignedBigInt {
+    type Output = SignedBigInt;
+    fn mul(self, rhs: &'a SignedBigInt) -> Self::Output {
+        SignedBigInt::from_parts(self.negative != rhs.negative, &self.magnitude * &rhs.magnitude)
+    }
+}
+
+/// Rounds towards zero. Use `div_euclid` for Euclidean division.
+impl<'a, 'b> ops::Div<&'a SignedBigInt> for &'b SignedBigInt {
+    type Output = SignedBigInt;
+    fn div(self, rhs: &'a SignedBigInt) -> Self::Output {
+        self.div_rem(rhs).0
+    }
+}
+
+/// The result has the sign of `self`. Use `rem_euclid` for a remainder that is never negative.
+impl<'a, 'b> ops::Rem<&'a SignedBigInt> for &'b SignedBigInt {
+    type Output = SignedBigInt;
+    fn rem(self, rhs: &'a SignedBigInt) -> Self::Output {
+        self.div_rem(rhs).1
+    }
+}
+
+// The remaining combinations of owned and borrowed operands all forward to the borrowed one above.
+macro_rules! forward_binop {
+    ($trait_:ident, $method:ident) => {
+        impl<'a> ops::$trait_<SignedBigInt> for &'a SignedBigInt {
+            type Output = SignedBigInt;
+            #[inline]
+            fn $method(self, rhs: SignedBigInt) -> Self::Output {
+                ops::$trait_::$method(self, &rhs)
+            }
+        }
+
+        impl<'a> ops::$trait_<&'a SignedBigInt> for SignedBigInt {
+            type Output = SignedBigInt;
+            #[inline]
+            fn $method(self, rhs: &'a SignedBigInt) -> Self::Output {
+                ops::$trait_::$method(&self, rhs)
+            }
+        }
+
+        impl ops::$trait_<SignedBigInt> for SignedBigInt {
+            type Output = SignedBigInt;
+            #[inline]
+            fn $method(self, rhs: SignedBigInt) -> Self::Output {
+                ops::$trait_::$method(&self, &rhs)
+            }
+        }
+    }
+}
+
+forward_binop!(Add, add);
+forward_binop!(Sub, sub);
+forward_binop!(Mul, mul);
+forward_binop!(Div, div);
+forward_binop!(Rem, rem);
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use bigint::BigInt;
+    use super::SignedBigInt;
+
+    fn s(x: i64) -> SignedBigInt {
+        SignedBigInt::from(x)
+    }
+
+    #[test]
+    fn test_invariant() {
+        assert_eq!(SignedBigInt::from_parts(true, BigInt::new(0)), s(0));
+        assert!(!(-s(0)).is_negative());
+        assert!((s(3) - s(3)).test_invariant());
+        assert!((s(-3) * s(0)).test_invariant());
+        assert!((s(-3) % s(3)).test_invariant());
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let values = [-100, -65, -7, -1, 0, 1, 5, 64, 1000];
+        for &a in values.iter() {
+            for &b in values.iter() {
+                assert_eq!(s(a) + s(b), s(a + b));
+                assert_eq!(s(a) - s(b), s(a - b));
+                assert_eq!(&s(a) * &s(b), s(a * b));
+            }
+            assert_eq!(-s(a), s(-a));
+        }
+        let big = SignedBigInt::from(BigInt::power_of_2(100));
+        assert_eq!(&big - &big - &big, -big.clone());
+        assert_eq!((&big - &big - &big) + s(1), -(big - s(1)));
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let values = [-100, -65, -7, -1, 1, 5, 64, 1000];
+        for &a in values.iter().chain([0].iter()) {
+            for &b in values.iter() {
+                assert_eq!(s(a).div_rem(&s(b)), (s(a / b), s(a % b)));
+                assert_eq!(s(a) / s(b), s(a / b));
+                assert_eq!(s(a) % s(b), s(a % b));
+                assert_eq!(s(a).div_rem_euclid(&s(b)), (s(a.div_euclid(b)), s(a.rem_euclid(b))));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Division of BigInt by zero")]
+    fn test_div_by_zero() {
+        let _ = s(-5) / s(0);
+    }
+
+    #[test]
+    fn test_conversions() {
+        for &x in [i128::MIN, i128::MIN + 1, i64::MIN as i128, -1, 0, 1, i64::MAX as i128, i128::MAX].iter() {
+            assert_eq!(i128::try_from(SignedBigInt::from(x)), Ok(x));
+        }
+        for &x in [i64::MIN, -1, 0, 1, i64::MAX].iter() {
+            assert_eq!(i64::try_from(SignedBigInt::from(x)), Ok(x));
+        }
+        assert!(i64::try_from(SignedBigInt::from(i64::MIN as i128 - 1)).is_err());
+        assert!(i64::try_from(SignedBigInt::from(i64::MAX as i128 + 1)).is_err());
+        assert!(i128::try_from(SignedBigInt::from(i128::MIN) - s(1)).is_err());
+        assert!(i128::try_from(SignedBigInt::from(i128::MAX) + s(1)).is_err());
+
+        let b = BigInt::power_of_2(130);
+        assert_eq!(BigInt::try_from(SignedBigInt::from(b.clone())), Ok(b.clone()));
+        assert!(BigInt::try_from(-SignedBigInt::from(b)).is_err());
+        assert_eq!(BigInt::try_from(-s(0)), Ok(BigInt::new(0)));
+    }
+
+    #[test]
+    fn test_ord() {
+        let mut v = vec![s(5), s(-3), s(0), s(-100), s(64)];
+        v.sort();
+        assert_eq!(v, vec![s(-100), s(-3), s(0), s(5), s(64)]);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(s(-42).to_string(), "-42");
+        assert_eq!(s(42).to_string(), "42");
+        assert_eq!(format!("{:+}", s(42)), "+42");
+        assert_eq!(format!("{:06}", s(-42)), "-00042");
+        assert_eq!(format!("{:>6}", s(-42)), "   -42");
+        assert_eq!((-SignedBigInt::from(BigInt::power_of_2(64))).to_string(), "-18446744073709551616");
+    }
+}
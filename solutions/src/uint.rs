@@ -0,0 +1,437 @@
+use std::{cmp, fmt};
+use std::convert::TryFrom;
+use bigint::{overflowing_add, overflowing_sub, mul_add, shr_limbs_in_place, BigInt, TryFromBigIntError};
+use bigint::{ct_step, ct_sub_borrow, ct_select_limbs, Montgomery};
+
+/// An unsigned integer of a fixed width of `64 * LIMBS` bits. Unlike `BigInt`, it does not grow: Arithmetic comes
+/// in wrapping, checked and overflowing variants, just like it does for the primitive integer types.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Uint<const LIMBS: usize> {
+    data: [u64; LIMBS], // least significant digits first
+}
+
+/// A 256-bit unsigned integer.
+pub type U256 = Uint<4>;
+/// A 512-bit unsigned integer.
+pub type U512 = Uint<8>;
+
+impl<const LIMBS: usize> Uint<LIMBS> {
+    /// The number of bits of this type.
+    pub const BITS: u32 = 64 * LIMBS as u32;
+    /// The smallest value, 0.
+    pub const ZERO: Self = Uint { data: [0; LIMBS] };
+    /// The largest value, `2^BITS - 1`.
+    pub const MAX: Self = Uint { data: [u64::MAX; LIMBS] };
+
+    /// Construct a number from a single digit.
+    pub fn new(x: u64) -> Self {
+        let mut result = Self::ZERO;
+        if LIMBS > 0 {
+            result.data[0] = x;
+        }
+        result
+    }
+
+    /// Construct a number from its digits, least significant digit first.
+    pub fn from_limbs(data: [u64; LIMBS]) -> Self {
+        Uint { data }
+    }
+
+    /// Return the digits, least significant digit first.
+    pub fn limbs(&self) -> &[u64; LIMBS] {
+        &self.data
+    }
+
+    /// Add `rhs`, returning the sum modulo `2^BITS` and whether the addition overflowed.
+    pub fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let mut result = Self::ZERO;
+        let mut carry = false;
+        for i in 0..LIMBS {
+            let (sum, new_carry) = overflowing_add(self.data[i], rhs.data[i], carry);
+            result.data[i] = sum;
+            carry = new_carry;
+        }
+        (result, carry)
+    }
+
+    /// Subtract `rhs`, returning the difference modulo `2^BITS` and whether the subtraction wrapped around.
+    pub fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let mut result = Self::ZERO;
+        let mut carry = false;
+        for i in 0..LIMBS {
+            let (diff, new_carry) = overflowing_sub(self.data[i], rhs.data[i], carry);
+            result.data[i] = diff;
+            carry = new_carry;
+        }
+        (result, carry)
+    }
+
+    /// Multiply by `rhs`, returning the product modulo `2^BITS` and whether the multiplication overflowed.
+    pub fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
+        let mut result = Self::ZERO;
+        let mut overflow = false;
+        for i in 0..LIMBS {
+            if self.data[i] == 0 {
+                continue;
+            }
+            // Schoolbook multiplication, but we only compute the digits that fit. Everything we drop is a
+            // non-negative contribution to the higher digits, so if any of it is non-zero, we overflowed.
+            let mut carry = 0;
+            for j in 0..LIMBS - i {
+                let (low, high) = mul_add(result.data[i + j], self.data[i], rhs.data[j], carry);
+                result.data[i + j] = low;
+                carry = high;
+            }
+            overflow |= carry != 0 || rhs.data[LIMBS - i..].iter().any(|&d| d != 0);
+        }
+        (result, overflow)
+    }
+
+    /// Shift to the left by `bits % BITS`, returning the result and whether `bits` was too large.
+    /// This matches the behavior of `overflowing_shl` on the primitive types: It is about the shift amount,
+    /// not about bits being shifted out.
+    pub fn overflowing_shl(self, bits: u32) -> (Self, bool) {
+        (self.shl_unchecked(bits % cmp::max(Self::BITS, 1)), bits >= Self::BITS)
+    }
+
+    /// Shift to the right by `bits % BITS`, returning the result and whether `bits` was too large.
+    pub fn overflowing_shr(self, bits: u32) -> (Self, bool) {
+        (self.shr_unchecked(bits % cmp::max(Self::BITS, 1)), bits >= Self::BITS)
+    }
+
+    // Shift to the left by `bits < BITS`.
+    fn shl_unchecked(self, bits: u32) -> Self {
+        let digits = (bits / 64) as usize;
+        let bits = bits % 64;
+        let mut result = Self::ZERO;
+        for i in (digits..LIMBS).rev() {
+            result.data[i] = self.data[i - digits] << bits;
+            if bits > 0 && i > digits {
+                result.data[i] |= self.data[i - digits - 1] >> (64 - bits);
+            }
+        }
+        result
+    }
+
+    // Shift to the right by `bits < BITS`.
+    fn shr_unchecked(self, bits: u32) -> Self {
+        let digits = (bits / 64) as usize;
+        let mut result = Self::ZERO;
+        result.data[..LIMBS - digits].copy_from_slice(&self.data[digits..]);
+        shr_limbs_in_place(&mut result.data, bits % 64);
+        result
+    }
+
+    /// Add `rhs`, wrapping around at `2^BITS`.
+    pub fn wrapping_add(self, rhs: Self) -> Self {
+        self.overflowing_add(rhs).0
+    }
+
+    /// Subtract `rhs`, wrapping around at 0.
+    pub fn wrapping_sub(self, rhs: Self) -> Self {
+        self.overflowing_sub(rhs).0
+    }
+
+    /// Multiply by `rhs`, wrapping around at `2^BITS`.
+    pub fn wrapping_mul(self, rhs: Self) -> Self {
+        self.overflowing_mul(rhs).0
+    }
+
+    /// Shift to the left by `bits % BITS`.
+    pub fn wrapping_shl(self, bits: u32) -> Self {
+        self.overflowing_shl(bits).0
+    }
+
+    /// Shift to the right by `bits % BITS`.
+    pub fn wrapping_shr(self, bits: u32) -> Self {
+        self.overflowing_shr(bits).0
+    }
+
+    /// Add `rhs`, returning `None` on overflow.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        checked(self.overflowing_add(rhs))
+    }
+
+    /// Subtract `rhs`, returning `None` if the result would be negative.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        checked(self.overflowing_sub(rhs))
+    }
+
+    /// Multiply by `rhs`, returning `None` on overflow.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        checked(self.overflowing_mul(rhs))
+    }
+
+    /// Shift to the left by `bits`, returning `None` if `bits >= BITS`.
+    pub fn checked_shl(self, bits: u32) -> Option<Self> {
+        checked(self.overflowing_shl(bits))
+    }
+
+    /// Shift to the right by `bits`, returning `None` if `bits >= BITS`.
+    pub fn checked_shr(self, bits: u32) -> Option<Self> {
+        checked(self.overflowing_shr(bits))
+    }
+}
+
+fn checked<T>((result, overflow): (T, bool)) -> Option<T> {
+    if overflow { None } else { Some(result) }
+}
+
+impl<const LIMBS: usize> Default for Uint<LIMBS> {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl<const LIMBS: usize> Ord for Uint<LIMBS> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.data.iter().rev().cmp(other.data.iter().rev())
+    }
+}
+
+impl<const LIMBS: usize> PartialOrd for Uint<LIMBS> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const LIMBS: usize> fmt::Display for Uint<LIMBS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&BigInt::from(*self), f)
+    }
+}
+
+impl<const LIMBS: usize> From<Uint<LIMBS>> for BigInt {
+    fn from(x: Uint<LIMBS>) -> Self {
+        BigInt::from_vec(x.data.to_vec())
+    }
+}
+
+impl<'a, const LIMBS: usize> TryFrom<&'a BigInt> for Uint<LIMBS> {
+    type Error = TryFromBigIntError;
+    fn try_from(b: &'a BigInt) -> Result<Self, TryFromBigIntError> {
+        if b.limbs().len() > LIMBS {
+            return Err(TryFromBigIntError::new());
+        }
+        let mut result = Self::ZERO;
+        for (dst, src) in result.data.iter_mut().zip(b.limbs()) {
+            *dst = src;
+        }
+        Ok(result)
+    }
+}
+
+impl<const LIMBS: usize> TryFrom<BigInt> for Uint<LIMBS> {
+    type Error = TryFromBigIntError;
+    fn try_from(b: BigInt) -> Result<Self, TryFromBigIntError> {
+        Uint::try_from(&b)
+    }
+}
+
+// Constant-time operations. They run the same instruction sequence for all values of their operands, which matters
+// when those are secrets: They never branch on or index memory by the digits. Only `LIMBS` and the modulus of
+// `ct_modpow` are public. A returned `bool` becomes public once the caller branches on it.
+//
+// Secrets have to stay in `Uint` from beginning to end. A `BigInt` only stores its significant digits, so its length
+// tells how large it is, and converting between `BigInt` and `Uint` copies as many digits as the `BigInt` has.
+impl<const LIMBS: usize> Uint<LIMBS> {
+    /// Compare for equality in constant time.
+    pub fn ct_eq(self, other: Self) -> bool {
+        let mut diff = 0;
+        for i in 0..LIMBS {
+            ct_step();
+            diff |= self.data[i] ^ other.data[i];
+        }
+        diff == 0
+    }
+
+    /// Return whether `self < other`, in constant time.
+    pub fn ct_lt(self, other: Self) -> bool {
+        // `self < other` iff computing `self - other` borrows in the end.
+        let mut borrow = 0;
+        for i in 0..LIMBS {
+            ct_step();
+            borrow = ct_sub_borrow(self.data[i], other.data[i], borrow).1;
+        }
+        borrow == 1
+    }
+
+    /// Return `a` if `choice` is set, and `b` otherwise, in constant time.
+    pub fn ct_select(a: Self, b: Self, choice: bool) -> Self {
+        let mut result = Self::ZERO;
+        ct_select_limbs(&mut result.data, &a.data, &b.data, choice as u64);
+        result
+    }
+
+    /// Compute `self^exp mod modulus` in constant time, processing all `BITS` bits of the exponent. The modulus is
+    /// public. Panics if it is even.
+    pub fn ct_modpow(self, exp: Self, modulus: Self) -> Self {
+        assert!(LIMBS > 0 && modulus.data[0] % 2 == 1,
+                "Constant-time modular exponentiation requires an odd modulus");
+        // We use Montgomery form with `R = 2^BITS`, so that all numbers keep their `LIMBS` digits, even if the
+        // modulus has fewer. We get there by multiplying with `R^2 mod modulus`, and back by multiplying with 1.
+        // Computing `R^2` is not constant-time, but it only depends on the modulus. Since `self < R`, the
+        // multiplication also takes care of reducing `self` modulo `modulus`.
+        let mont = Montgomery::new(&modulus.data);
+        let r_squared = BigInt::power_of_2(2 * Self::BITS as u64) % BigInt::from(modulus);
+        let r_squared = Self::try_from(r_squared).unwrap().data;
+        let one = Self::new(1).data;
+        let base = mont.mul_ct(&self.data, &r_squared);
+        let mut result = mont.mul_ct(&one, &r_squared);
+        // Square-and-always-multiply: We compute the product for every bit, and select whether to keep it.
+        for i in (0..64 * LIMBS).rev() {
+            ct_step();
+            result = mont.mul_ct(&result, &result);
+            let product = mont.mul_ct(&result, &base);
+            let bit = (exp.data[i / 64] >> (i % 64)) & 1;
+            let squared = result.clone();
+            ct_select_limbs(&mut result, &product, &squared, bit);
+        }
+        let mut normal = Self::ZERO;
+        normal.data.copy_from_slice(&mont.mul_ct(&result, &one));
+        normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use bigint::BigInt;
+    use rng::XorShiftRng;
+    use bigint::CT_STEPS;
+    use super::{Uint, U256, U512};
+
+    fn random_u256(rng: &mut XorShiftRng) -> U256 {
+        // Mix in some zero and all-ones digits to hit the carry paths.
+        let mut data = [0; 4];
+        for d in data.iter_mut() {
+            *d = match rng.next_u64() % 4 {
+                0 => 0,
+                1 => u64::MAX,
+                _ => rng.next_u64(),
+            };
+        }
+        U256::from_limbs(data)
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let one = U256::new(1);
+        assert_eq!(U256::MAX.overflowing_add(one), (U256::ZERO, true));
+        assert_eq!(U256::ZERO.overflowing_sub(one), (U256::MAX, true));
+        assert_eq!(U256::MAX.checked_add(one), None);
+        assert_eq!(U256::new(5).checked_sub(U256::new(3)), Some(U256::new(2)));
+        assert_eq!(U256::from_limbs([u64::MAX, 0, 0, 0]).wrapping_add(one), U256::from_limbs([0, 1, 0, 0]));
+    }
+
+    #[test]
+    fn test_mul() {
+        let two_128 = U256::from_limbs([0, 0, 1, 0]);
+        assert_eq!(two_128.overflowing_mul(two_128), (U256::ZERO, true));
+        assert_eq!(U256::MAX.overflowing_mul(U256::MAX), (U256::new(1), true));
+        assert_eq!(U256::MAX.checked_mul(U256::new(1)), Some(U256::MAX));
+        assert_eq!(U256::ZERO.checked_mul(U256::MAX), Some(U256::ZERO));
+        let two_64 = U256::from_limbs([0, 1, 0, 0]);
+        assert_eq!(two_128.checked_mul(two_64), Some(U256::from_limbs([0, 0, 0, 1])));
+    }
+
+    #[test]
+    fn test_shift() {
+        let x = U256::from_limbs([1 << 63, 3, 0, 1]);
+        assert_eq!(x.wrapping_shl(65), U256::from_limbs([0, 0, 7, 0]));
+        assert_eq!(x.wrapping_shr(63), U256::from_limbs([7, 0, 2, 0]));
+        assert_eq!(x.overflowing_shl(256), (x, true));
+        assert_eq!(x.overflowing_shr(257), (x.wrapping_shr(1), true));
+        assert_eq!(x.checked_shl(192), Some(U256::from_limbs([0, 0, 0, 1 << 63])));
+        assert_eq!(x.checked_shr(256), None);
+    }
+
+    #[test]
+    fn test_against_bigint() {
+        let mut rng = XorShiftRng::new(256);
+        let modulus = BigInt::power_of_2(256);
+        for _ in 0..1000 {
+            let (a, b) = (random_u256(&mut rng), random_u256(&mut rng));
+            let (big_a, big_b) = (BigInt::from(a), BigInt::from(b));
+            assert_eq!(U256::try_from(&big_a), Ok(a));
+
+            let sum = &big_a + &big_b;
+            assert_eq!(a.overflowing_add(b), (U256::try_from(&sum % &modulus).unwrap(), sum >= modulus));
+            let product = &big_a * &big_b;
+            assert_eq!(a.overflowing_mul(b), (U256::try_from(&product % &modulus).unwrap(), product >= modulus));
+            let difference = if big_a >= big_b { Some(&big_a - &big_b) } else { None };
+            assert_eq!(a.checked_sub(b).map(BigInt::from), difference);
+            let shift = (rng.next_u64() % 256) as u32;
+            assert_eq!(BigInt::from(a.wrapping_shl(shift)), (&big_a << shift as u64) % &modulus);
+            assert_eq!(BigInt::from(a.wrapping_shr(shift)), &big_a >> shift as u64);
+            assert_eq!(a.cmp(&b), big_a.cmp(&big_b));
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert!(U256::try_from(BigInt::power_of_2(256)).is_err());
+        assert_eq!(U512::try_from(BigInt::power_of_2(256)), Ok(U512::new(1).wrapping_shl(256)));
+        assert_eq!(U256::MAX.to_string(), (BigInt::power_of_2(256) - BigInt::new(1)).to_string());
+        assert_eq!(format!("{:>5}", Uint::<2>::new(42)), "   42");
+    }
+
+    #[test]
+    fn test_ct_ops() {
+        let mut rng = XorShiftRng::new(2020);
+        // The first and last modulus have fewer significant digits than the width, so they come with leading zeros.
+        let moduli = [U256::from_limbs([0xffff_ffff_0000_0001, 0xfedc_ba98_7654_3210, 0x1234_5678, 0]),
+                      U256::MAX, U256::new(7)];
+        for _ in 0..100 {
+            let a = random_u256(&mut rng);
+            let b = if rng.next_u64() % 4 == 0 { a } else { random_u256(&mut rng) };
+            assert_eq!(a.ct_eq(b), a == b);
+            assert_eq!(a.ct_lt(b), a < b);
+            assert_eq!(U256::ct_select(a, b, true), a);
+            assert_eq!(U256::ct_select(a, b, false), b);
+            for &modulus in moduli.iter() {
+                let expected = BigInt::from(a).modpow(&BigInt::from(b), &BigInt::from(modulus));
+                assert_eq!(BigInt::from(a.ct_modpow(b, modulus)), expected);
+            }
+        }
+        assert_eq!(Uint::<1>::new(5).ct_modpow(Uint::new(0), Uint::new(1)), Uint::new(0));
+        assert_eq!(Uint::<1>::new(3).ct_modpow(Uint::new(4), Uint::new(7)), Uint::new(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "Constant-time modular exponentiation requires an odd modulus")]
+    fn test_ct_modpow_even() {
+        U256::new(3).ct_modpow(U256::new(3), U256::new(10));
+    }
+
+    // Count the loop iterations of `f`.
+    fn ct_steps<F: FnOnce()>(f: F) -> u64 {
+        CT_STEPS.with(|steps| steps.set(0));
+        f();
+        CT_STEPS.with(|steps| steps.get())
+    }
+
+    #[test]
+    fn test_ct_steps() {
+        // As `BigInt`s, these would have anywhere between 0 and 4 digits, and so would the results. Small results,
+        // such as 0 and 1 from `ct_modpow`, must not take a shortcut either.
+        let modulus = U256::from_limbs([0xffff_ffff_0000_0001, 0xfedc_ba98_7654_3210, 0x1234_5678, 0]);
+        let values = [U256::ZERO, U256::new(1), U256::MAX, U256::from_limbs([0, 0, 0, 1 << 63]),
+                      U256::from_limbs([42, 0, 7, 0])];
+        let mut counts = Vec::new();
+        for &a in values.iter() {
+            for &b in values.iter() {
+                counts.push((
+                    ct_steps(|| { a.ct_eq(b); }),
+                    ct_steps(|| { a.ct_lt(b); }),
+                    ct_steps(|| { U256::ct_select(a, b, a < b); }),
+                    ct_steps(|| { a.ct_modpow(b, modulus); }),
+                ));
+            }
+        }
+        assert!(counts.iter().all(|&c| c == counts[0]), "{:?}", counts);
+        assert_eq!(counts[0].0, 4);
+        assert_eq!(counts[0].2, 4);
+        assert!(counts[0].3 >= 64 * 4);
+    }
+}
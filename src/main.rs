@@ -101,6 +101,9 @@ mod part14;
 mod part15;
 mod part16;
 
+// The regular expression engine that rgrep can use (see part 13).
+mod regex;
+
 // To actually run the code of some part (after filling in the blanks, if necessary), simply edit the `main`
 // function.
 fn main() {
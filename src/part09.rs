@@ -24,6 +24,7 @@ use part05::BigInt;
 pub struct Iter<'a> {
     num: &'a BigInt,
     idx: usize, // the index of the last number that was returned
+    back_taken: usize, // the number of digits that were returned from the back (see below)
 }
 
 // Now we are equipped to implement `Iterator` for `Iter`.
@@ -33,7 +34,7 @@ impl<'a> Iterator for Iter<'a> {
 
     fn next(&mut self) -> Option<u64> {
         // First, check whether there's any more digits to return.
-        if self.idx == 0 {
+        if self.idx == self.back_taken {
             // We already returned all the digits, nothing to do.
             None                                                    /*@*/
         } else {
@@ -42,6 +43,13 @@ impl<'a> Iterator for Iter<'a> {
             Some(self.num.data[self.idx])                           /*@*/
         }
     }
+
+    //@ `size_hint` is one of the provided methods. By default, it does not know anything about the length of the iterator,
+    //@ but we can easily do better: We know exactly how many digits are left.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.idx - self.back_taken;
+        (len, Some(len))
+    }
 }
 
 // All we need now is a function that creates such an iterator for a given `BigInt`.
@@ -50,7 +58,7 @@ impl BigInt {
     //@ the case with functions returning references, you can elide the lifetime. The rules for adding the lifetimes are exactly the
     //@ same. (See the last section of [part 06](part06.html).)
     fn iter(&self) -> Iter {
-        Iter { num: self, idx: self.data.len() }                    /*@*/
+        Iter { num: self, idx: self.data.len(), back_taken: 0 }     /*@*/
     }
 }
 
@@ -92,6 +100,27 @@ fn print_digits_v2(b: &BigInt) {
 // **Exercise 09.2**: Write a function `iter_ldf` that iterators over the digits with the least-significant
 // digits coming first. Write a testcase for it.
 
+// ## Iterating from both ends
+//@ There is a more general way to solve the last exercise: An iterator can also implement
+//@ [`DoubleEndedIterator`](https://doc.rust-lang.org/stable/std/iter/trait.DoubleEndedIterator.html), which means that
+//@ it can hand out elements from the back, too. For `Iter`, the back is the least-significant digit. The two ends meet
+//@ in the middle, which is why `next` above checks against `back_taken` rather than 0.
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<u64> {
+        if self.back_taken == self.idx {
+            None                                                    /*@*/
+        } else {
+            self.back_taken = self.back_taken + 1;                  /*@*/
+            Some(self.num.data[self.back_taken - 1])                /*@*/
+        }
+    }
+}
+//@ Now `b.iter().rev()` iterates over the digits with the least-significant one first, and `next` and `next_back` can
+//@ even be mixed. Since our `size_hint` is exact, we can also promise that by implementing
+//@ [`ExactSizeIterator`](https://doc.rust-lang.org/stable/std/iter/trait.ExactSizeIterator.html). All its methods
+//@ have default implementations, so we do not have to write anything.
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
 // ## Iterator invalidation and lifetimes
 //@ You may have been surprised that we had to explicitly annotate a lifetime when we wrote `Iter`. Of
 //@ course, with lifetimes being present at every reference in Rust, this is only consistent. But do we at
@@ -5,6 +5,7 @@ use std::io::prelude::*;
 use std::{io, fs, thread};
 use std::sync::mpsc::{sync_channel, SyncSender, Receiver};
 use std::sync::Arc;
+use regex::Regex;
 
 //@ Our next stop are the concurrency features of Rust. We are going to write our own small version of "grep",
 //@ called *rgrep*, and it is going to perform three jobs concurrently: One thread reads the input files, one thread does
@@ -26,6 +27,7 @@ use self::OutputMode::*;
 pub struct Options {
     pub files: Vec<String>,
     pub pattern: String,
+    pub regex: Option<Regex>, // if this is `None`, we search for `pattern` literally
     pub output_mode: OutputMode,
 }
 
@@ -66,7 +68,12 @@ fn filter_lines(options: Arc<Options>,
     for line in in_channel.iter() {
         // `contains` works on lots of types of patterns, but in particular, we can use it to test whether
         // one string is contained in another. This is another example of Rust using traits as substitute for overloading.
-        if line.contains(&options.pattern) {
+        // If we were given a regular expression, we use the engine in `regex.rs` instead.
+        let is_match = match options.regex {
+            Some(ref regex) => regex.is_match(&line),
+            None => line.contains(&options.pattern),
+        };
+        if is_match {
             out_channel.send(line).unwrap();                        /*@*/
         }
     }
@@ -140,6 +147,7 @@ pub fn main() {
                     "src/part11.rs".to_string(),
                     "src/part12.rs".to_string()],
         pattern: "let".to_string(),
+        regex: None,
         output_mode: Print
     };
     run(options);
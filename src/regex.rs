@@ -0,0 +1,728 @@
+use std::{fmt, error};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// The kinds of syntax errors that can occur in a regular expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegexErrorKind {
+    /// A `(` without the matching `)`.
+    UnclosedGroup,
+    /// A `)` without the matching `(`.
+    UnmatchedParen,
+    /// A `[` without the matching `]`.
+    UnclosedClass,
+    /// A range like `z-a` in a character class, whose end comes before its start.
+    InvalidRange,
+    /// A repetition operator with nothing to repeat, like `*a`.
+    MissingOperand,
+    /// A counted repetition that is not of the form `{m}`, `{m,}` or `{m,n}` with `m <= n`.
+    InvalidRepetition,
+    /// A counted repetition above `MAX_REPEAT`, or a pattern that becomes too large through repetition.
+    TooLarge,
+    /// A `\` at the end of the pattern.
+    TrailingBackslash,
+    /// An escape sequence like `\q` that has no meaning.
+    UnknownEscape,
+}
+
+/// The error returned when a regular expression cannot be parsed. It knows where in the pattern things went wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexError {
+    kind: RegexErrorKind,
+    column: usize,
+}
+
+impl RegexError {
+    /// Return what went wrong.
+    pub fn kind(&self) -> RegexErrorKind {
+        self.kind
+    }
+
+    /// Return the column (counting characters, starting at 1) of the pattern at which the error was found.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self.kind {
+            RegexErrorKind::UnclosedGroup => "unclosed group",
+            RegexErrorKind::UnmatchedParen => "unmatched closing parenthesis",
+            RegexErrorKind::UnclosedClass => "unclosed character class",
+            RegexErrorKind::InvalidRange => "invalid range in character class",
+            RegexErrorKind::MissingOperand => "repetition operator without operand",
+            RegexErrorKind::InvalidRepetition => "invalid counted repetition",
+            RegexErrorKind::TooLarge => "pattern too large",
+            RegexErrorKind::TrailingBackslash => "trailing backslash",
+            RegexErrorKind::UnknownEscape => "unknown escape sequence",
+        };
+        write!(f, "{} at column {}", description, self.column)
+    }
+}
+
+impl error::Error for RegexError {}
+
+/// The largest count allowed in a counted repetition `{m,n}`.
+pub const MAX_REPEAT: u32 = 1000;
+// The largest number of instructions a compiled pattern may have.
+const MAX_PROGRAM_LEN: usize = 100_000;
+// The lazy DFA throws away its states and starts over when it has more than this many of them.
+const MAX_DFA_STATES: usize = 4096;
+
+// The predefined classes `\d`, `\w` and `\s`.
+#[derive(Debug, Clone, Copy)]
+enum Perl {
+    Digit,
+    Word,
+    Space,
+}
+
+impl Perl {
+    fn matches(self, c: char) -> bool {
+        match self {
+            Perl::Digit => c.is_ascii_digit(),
+            Perl::Word => c.is_ascii_alphanumeric() || c == '_',
+            Perl::Space => c.is_whitespace(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Perl(Perl, bool), // negated if the flag is set
+}
+
+// Something that matches a single character.
+#[derive(Debug, Clone)]
+enum Atom {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool), // negated if the flag is set
+}
+
+impl Atom {
+    fn matches(&self, c: char, case_insensitive: bool) -> bool {
+        // With `case_insensitive`, we also try the other cases of `c`. For classes, this has to happen before the
+        // negation: `[^a]` must not match `A` then.
+        let mut variants = c.to_lowercase().chain(c.to_uppercase()).filter(|&d| d != c);
+        match *self {
+            Atom::Char(d) => c == d || (case_insensitive && variants.any(|c| c == d)),
+            Atom::Any => true,
+            Atom::Class(ref items, negated) => {
+                let contains = |c: char| items.iter().any(|item| match *item {
+                    ClassItem::Range(lo, hi) => lo <= c && c <= hi,
+                    ClassItem::Perl(perl, negated) => perl.matches(c) != negated,
+                });
+                let found = contains(c) || (case_insensitive && variants.any(contains));
+                found != negated
+            }
+        }
+    }
+}
+
+// The syntax tree of a regular expression.
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Atom(Atom),
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat { node: Box<Node>, min: u32, max: Option<u32> },
+}
+
+// What follows a backslash.
+enum Escape {
+    Literal(char),
+    Perl(Perl, bool),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, kind: RegexErrorKind, pos: usize) -> RegexError {
+        RegexError { kind, column: pos + 1 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    fn parse(pattern: &str) -> Result<Node, RegexError> {
+        let mut parser = Parser { chars: pattern.chars().collect(), pos: 0 };
+        let node = parser.parse_alt()?;
+        // `parse_alt` only stops early at a `)` that does not belong to any group.
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error(RegexErrorKind::UnmatchedParen, parser.pos));
+        }
+        Ok(node)
+    }
+
+    fn parse_alt(&mut self) -> Result<Node, RegexError> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Node::Alt(branches) })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, RegexError> {
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some('|') | Some(')') => break,
+                Some(_) => {
+                    let atom = self.parse_atom()?;
+                    items.push(self.parse_repetitions(atom)?);
+                }
+            }
+        }
+        Ok(match items.len() {
+            0 => Node::Empty,
+            1 => items.pop().unwrap(),
+            _ => Node::Concat(items),
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexError> {
+        let start = self.pos;
+        Ok(match self.next().unwrap() {
+            '(' => {
+                // Groups do not capture anything, so `(?:...)` is just the same as `(...)`.
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                let inner = self.parse_alt()?;
+                if self.next() != Some(')') {
+                    return Err(self.error(RegexErrorKind::UnclosedGroup, start));
+                }
+                inner
+            }
+            '^' => Node::Start,
+            '$' => Node::End,
+            '.' => Node::Atom(Atom::Any),
+            '[' => Node::Atom(self.parse_class(start)?),
+            '\\' => match self.parse_escape(start)? {
+                Escape::Literal(c) => Node::Atom(Atom::Char(c)),
+                Escape::Perl(perl, negated) => Node::Atom(Atom::Class(vec![ClassItem::Perl(perl, negated)], false)),
+            },
+            '*' | '+' | '?' | '{' => return Err(self.error(RegexErrorKind::MissingOperand, start)),
+            c => Node::Atom(Atom::Char(c)),
+        })
+    }
+
+    // Parse the escape sequence whose backslash is at `start`. The backslash has already been consumed.
+    fn parse_escape(&mut self, start: usize) -> Result<Escape, RegexError> {
+        let c = match self.next() {
+            Some(c) => c,
+            None => return Err(self.error(RegexErrorKind::TrailingBackslash, start)),
+        };
+        Ok(match c {
+            'd' => Escape::Perl(Perl::Digit, false),
+            'D' => Escape::Perl(Perl::Digit, true),
+            'w' => Escape::Perl(Perl::Word, false),
+            'W' => Escape::Perl(Perl::Word, true),
+            's' => Escape::Perl(Perl::Space, false),
+            'S' => Escape::Perl(Perl::Space, true),
+            'n' => Escape::Literal('\n'),
+            't' => Escape::Literal('\t'),
+            'r' => Escape::Literal('\r'),
+            c if !c.is_alphanumeric() => Escape::Literal(c),
+            _ => return Err(self.error(RegexErrorKind::UnknownEscape, start)),
+        })
+    }
+
+    // Parse a character class whose `[` is at `start`. The bracket has already been consumed.
+    fn parse_class(&mut self, start: usize) -> Result<Atom, RegexError> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let pos = self.pos;
+            let lo = match self.next() {
+                None => return Err(self.error(RegexErrorKind::UnclosedClass, start)),
+                // A `]` right at the beginning is taken literally.
+                Some(']') if !first => break,
+                Some('\\') if self.peek().is_none() => return Err(self.error(RegexErrorKind::UnclosedClass, start)),
+                Some('\\') => match self.parse_escape(pos)? {
+                    Escape::Literal(c) => c,
+                    Escape::Perl(perl, negated) => {
+                        items.push(ClassItem::Perl(perl, negated));
+                        first = false;
+                        continue;
+                    }
+                },
+                Some(c) => c,
+            };
+            first = false;
+            // A `-` is a range, unless it is the last character of the class.
+            if self.peek() == Some('-') && matches!(self.chars.get(self.pos + 1), Some(&c) if c != ']') {
+                self.pos += 1;
+                let hi_pos = self.pos;
+                let hi = match self.next().unwrap() {
+                    '\\' => match self.parse_escape(hi_pos)? {
+                        Escape::Literal(c) => c,
+                        Escape::Perl(..) => return Err(self.error(RegexErrorKind::InvalidRange, pos)),
+                    },
+                    c => c,
+                };
+                if hi < lo {
+                    return Err(self.error(RegexErrorKind::InvalidRange, pos));
+                }
+                items.push(ClassItem::Range(lo, hi));
+            } else {
+                items.push(ClassItem::Range(lo, lo));
+            }
+        }
+        Ok(Atom::Class(items, negated))
+    }
+
+    fn parse_repetitions(&mut self, mut node: Node) -> Result<Node, RegexError> {
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => { self.pos += 1; (0, None) }
+                Some('+') => { self.pos += 1; (1, None) }
+                Some('?') => { self.pos += 1; (0, Some(1)) }
+                Some('{') => self.parse_counted()?,
+                _ => return Ok(node),
+            };
+            node = Node::Repeat { node: Box::new(node), min, max };
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        let mut n: u32 = 0;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            self.pos += 1;
+            // Overflowing numbers are certainly too large, so we just saturate.
+            n = n.saturating_mul(10).saturating_add(digit);
+        }
+        if self.pos == start { None } else { Some(n) }
+    }
+
+    // Parse `{m}`, `{m,}` or `{m,n}`.
+    fn parse_counted(&mut self) -> Result<(u32, Option<u32>), RegexError> {
+        let start = self.pos;
+        self.pos += 1;
+        let invalid = |parser: &Parser| parser.error(RegexErrorKind::InvalidRepetition, start);
+        let min = self.parse_number().ok_or_else(|| invalid(self))?;
+        let max = if self.peek() == Some(',') {
+            self.pos += 1;
+            self.parse_number()
+        } else {
+            Some(min)
+        };
+        if self.next() != Some('}') || matches!(max, Some(max) if max < min) {
+            return Err(invalid(self));
+        }
+        if min > MAX_REPEAT || matches!(max, Some(max) if max > MAX_REPEAT) {
+            return Err(self.error(RegexErrorKind::TooLarge, start));
+        }
+        Ok((min, max))
+    }
+}
+
+// The instructions of a Thompson NFA.
+#[derive(Debug)]
+enum Inst {
+    Atom(Atom),
+    Split(usize, usize),
+    Jmp(usize),
+    AssertStart,
+    AssertEnd,
+    Match,
+}
+
+#[derive(Debug)]
+struct Program {
+    insts: Vec<Inst>,
+    case_insensitive: bool,
+}
+
+impl Program {
+    fn compile(node: &Node, case_insensitive: bool) -> Result<Program, RegexError> {
+        let mut program = Program { insts: Vec::new(), case_insensitive };
+        program.emit(node)?;
+        program.insts.push(Inst::Match);
+        Ok(program)
+    }
+
+    fn push(&mut self, inst: Inst) -> Result<usize, RegexError> {
+        if self.insts.len() >= MAX_PROGRAM_LEN {
+            return Err(RegexError { kind: RegexErrorKind::TooLarge, column: 1 });
+        }
+        self.insts.push(inst);
+        Ok(self.insts.len() - 1)
+    }
+
+    fn emit(&mut self, node: &Node) -> Result<(), RegexError> {
+        match *node {
+            Node::Empty => {}
+            Node::Atom(ref atom) => { self.push(Inst::Atom(atom.clone()))?; }
+            Node::Start => { self.push(Inst::AssertStart)?; }
+            Node::End => { self.push(Inst::AssertEnd)?; }
+            Node::Concat(ref items) => {
+                for item in items {
+                    self.emit(item)?;
+                }
+            }
+            Node::Alt(ref branches) => {
+                // Every branch but the last one starts with a split that skips it, and ends with a jump to the end.
+                let mut jumps = Vec::new();
+                let (last, init) = branches.split_last().unwrap();
+                for branch in init {
+                    let split = self.push(Inst::Split(0, 0))?;
+                    self.emit(branch)?;
+                    jumps.push(self.push(Inst::Jmp(0))?);
+                    self.insts[split] = Inst::Split(split + 1, self.insts.len());
+                }
+                self.emit(last)?;
+                let end = self.insts.len();
+                for jump in jumps {
+                    self.insts[jump] = Inst::Jmp(end);
+                }
+            }
+            Node::Repeat { ref node, min, max } => {
+                for _ in 0..min {
+                    self.emit(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.emit(node)?;
+                        self.push(Inst::Jmp(split))?;
+                        self.insts[split] = Inst::Split(split + 1, self.insts.len());
+                    }
+                    Some(max) => {
+                        // Every optional copy can skip to the very end.
+                        let mut splits = Vec::new();
+                        for _ in min..max {
+                            splits.push(self.push(Inst::Split(0, 0))?);
+                            self.emit(node)?;
+                        }
+                        let end = self.insts.len();
+                        for split in splits {
+                            self.insts[split] = Inst::Split(split + 1, end);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Follow all the empty transitions from `seeds`, and return the (sorted) set of instructions that consume a
+    // character or finish the match. `AssertEnd` is kept in the set unless we know that we are at the end.
+    fn closure(&self, seeds: &[usize], at_start: bool, at_end: bool) -> Vec<usize> {
+        let mut seen = vec![false; self.insts.len()];
+        let mut stack = seeds.to_vec();
+        let mut result = Vec::new();
+        while let Some(pc) = stack.pop() {
+            if seen[pc] {
+                continue;
+            }
+            seen[pc] = true;
+            match self.insts[pc] {
+                Inst::Atom(_) | Inst::Match => result.push(pc),
+                Inst::Split(a, b) => {
+                    stack.push(b);
+                    stack.push(a);
+                }
+                Inst::Jmp(a) => stack.push(a),
+                Inst::AssertStart => if at_start { stack.push(pc + 1) },
+                Inst::AssertEnd => if at_end { stack.push(pc + 1) } else { result.push(pc) },
+            }
+        }
+        result.sort();
+        result
+    }
+
+    fn contains_match(&self, pcs: &[usize]) -> bool {
+        pcs.iter().any(|&pc| matches!(self.insts[pc], Inst::Match))
+    }
+}
+
+const UNKNOWN: usize = usize::MAX;
+
+// A state of the lazy DFA: a set of NFA instructions, and the transitions we computed so far.
+struct State {
+    pcs: Vec<usize>,
+    is_match: bool,
+    matches_at_end: bool,
+    ascii: [usize; 128],
+    other: HashMap<char, usize>,
+}
+
+// The lazy DFA. States are only constructed when the search needs them, and then cached.
+struct Dfa {
+    states: Vec<State>,
+    index: HashMap<Vec<usize>, usize>,
+    start: usize,
+}
+
+impl Dfa {
+    fn new(program: &Program) -> Dfa {
+        let mut dfa = Dfa { states: Vec::new(), index: HashMap::new(), start: 0 };
+        dfa.start = dfa.add_state(program, program.closure(&[0], true, false));
+        dfa
+    }
+
+    fn add_state(&mut self, program: &Program, pcs: Vec<usize>) -> usize {
+        if let Some(&id) = self.index.get(&pcs) {
+            return id;
+        }
+        let is_match = program.contains_match(&pcs);
+        let after_end: Vec<usize> = pcs.iter().filter(|&&pc| matches!(program.insts[pc], Inst::AssertEnd))
+            .map(|&pc| pc + 1).collect();
+        let matches_at_end = is_match || program.contains_match(&program.closure(&after_end, false, true));
+        let id = self.states.len();
+        self.index.insert(pcs.clone(), id);
+        self.states.push(State { pcs, is_match, matches_at_end, ascii: [UNKNOWN; 128], other: HashMap::new() });
+        id
+    }
+
+    // Return the state reached from `state` by reading `c`. This may throw away all other states.
+    fn next(&mut self, program: &Program, state: usize, c: char) -> usize {
+        let cached = if (c as usize) < 128 {
+            self.states[state].ascii[c as usize]
+        } else {
+            self.states[state].other.get(&c).cloned().unwrap_or(UNKNOWN)
+        };
+        if cached != UNKNOWN {
+            return cached;
+        }
+        // Advance all threads over `c`, and start a new thread here: The match can begin anywhere.
+        let mut seeds: Vec<usize> = self.states[state].pcs.iter()
+            .filter(|&&pc| match program.insts[pc] {
+                Inst::Atom(ref atom) => atom.matches(c, program.case_insensitive),
+                _ => false,
+            })
+            .map(|&pc| pc + 1).collect();
+        seeds.push(0);
+        let pcs = program.closure(&seeds, false, false);
+        if self.states.len() >= MAX_DFA_STATES && !self.index.contains_key(&pcs) {
+            // The cache is full. Start over; we do not need to remember the transition then.
+            *self = Dfa::new(program);
+            return self.add_state(program, pcs);
+        }
+        let next = self.add_state(program, pcs);
+        if (c as usize) < 128 {
+            self.states[state].ascii[c as usize] = next;
+        } else {
+            self.states[state].other.insert(c, next);
+        }
+        next
+    }
+}
+
+/// A compiled regular expression. It is compiled into an NFA, which is turned into a DFA lazily while matching.
+///
+/// The syntax supports literal characters, `.`, character classes like `[a-z_]` and `[^0-9]`, the escapes `\d`,
+/// `\w`, `\s` (and their negations `\D`, `\W`, `\S`), the anchors `^` and `$`, alternation `a|b`, groups `(...)`
+/// and the repetitions `*`, `+`, `?`, `{m}`, `{m,}` and `{m,n}`.
+pub struct Regex {
+    pattern: String,
+    program: Arc<Program>,
+    dfa: Mutex<Dfa>,
+}
+
+impl Regex {
+    /// Compile the given pattern.
+    pub fn new(pattern: &str) -> Result<Regex, RegexError> {
+        Regex::with_case(pattern, false)
+    }
+
+    /// Compile the given pattern, such that it matches independently of upper and lower case.
+    pub fn new_ignore_case(pattern: &str) -> Result<Regex, RegexError> {
+        Regex::with_case(pattern, true)
+    }
+
+    fn with_case(pattern: &str, case_insensitive: bool) -> Result<Regex, RegexError> {
+        let program = Program::compile(&Parser::parse(pattern)?, case_insensitive)?;
+        let dfa = Dfa::new(&program);
+        Ok(Regex { pattern: pattern.to_string(), program: Arc::new(program), dfa: Mutex::new(dfa) })
+    }
+
+    /// Return the pattern this regular expression was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Return whether the pattern matches somewhere in `text`. `^` and `$` match at the beginning and end of `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        let program = &*self.program;
+        if text.is_empty() {
+            return program.contains_match(&program.closure(&[0], true, true));
+        }
+        let mut dfa = self.dfa.lock().unwrap();
+        let mut state = dfa.start;
+        for c in text.chars() {
+            if dfa.states[state].is_match {
+                return true;
+            }
+            state = dfa.next(program, state, c);
+        }
+        dfa.states[state].matches_at_end
+    }
+}
+
+/// Cloning a `Regex` shares the compiled program, but the clone builds its own DFA. Give every thread its own clone
+/// to avoid contention.
+impl Clone for Regex {
+    fn clone(&self) -> Self {
+        Regex { pattern: self.pattern.clone(), program: self.program.clone(), dfa: Mutex::new(Dfa::new(&self.program)) }
+    }
+}
+
+impl fmt::Debug for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Regex({:?})", self.pattern)
+    }
+}
+
+/// Escape all characters of `text` that have a special meaning in a pattern, so that it matches literally.
+pub fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Regex, RegexErrorKind, escape, MAX_DFA_STATES};
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text)
+    }
+
+    fn error(pattern: &str) -> (RegexErrorKind, usize) {
+        let err = Regex::new(pattern).unwrap_err();
+        (err.kind(), err.column())
+    }
+
+    #[test]
+    fn test_literal() {
+        assert!(matches("abc", "xxabcxx"));
+        assert!(!matches("abc", "ab c"));
+        assert!(matches("", ""));
+        assert!(matches("", "abc"));
+        assert!(matches("a.c", "abc") && matches("a.c", "a☃c"));
+        assert!(matches(r"a\.c", "a.c") && !matches(r"a\.c", "abc"));
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(matches("[abc]x", "cx") && !matches("[abc]x", "dx"));
+        assert!(matches("^[a-z_]+$", "snake_case") && !matches("^[a-z_]+$", "camelCase"));
+        assert!(matches("[^0-9]", "12a") && !matches("^[^0-9]+$", "12a"));
+        assert!(matches("[]]", "]") && matches("[a-]", "-") && matches(r"[\]]", "]"));
+        assert!(matches(r"^\d+\s\w+$", "42 apples") && !matches(r"^\d+$", "4x2"));
+        assert!(matches(r"^[\d.]+$", "3.14") && matches(r"^\D\W\S$", "a-b"));
+    }
+
+    #[test]
+    fn test_anchors() {
+        assert!(matches("^abc", "abcd") && !matches("^abc", "xabc"));
+        assert!(matches("abc$", "xabc") && !matches("abc$", "abcx"));
+        assert!(matches("^$", "") && !matches("^$", "a"));
+        assert!(matches("a|^b", "xa") && !matches("^b|c$", "xbx"));
+        assert!(matches("(^|,)x", "a,x") && matches("(^|,)x", "x") && !matches("(^|,)x", "ax"));
+        assert!(matches("x($|,)", "x") && matches("x($|,)", "x,y") && !matches("x($|,)", "xy"));
+    }
+
+    #[test]
+    fn test_alternation_groups() {
+        assert!(matches("cat|dog", "hotdog") && !matches("cat|dog", "cow"));
+        assert!(matches("^(ab|cd)+$", "abcdab") && !matches("^(ab|cd)+$", "abc"));
+        assert!(matches("^(?:a|)b$", "b") && matches("^a(|b)$", "ab"));
+    }
+
+    #[test]
+    fn test_repetitions() {
+        assert!(matches("^ab*c$", "ac") && matches("^ab*c$", "abbbc"));
+        assert!(!matches("^ab+c$", "ac") && matches("^ab+c$", "abc"));
+        assert!(matches("^ab?c$", "ac") && !matches("^ab?c$", "abbc"));
+        assert!(matches("^a{3}$", "aaa") && !matches("^a{3}$", "aa") && !matches("^a{3}$", "aaaa"));
+        assert!(matches("^a{2,}$", "aaaaa") && !matches("^a{2,}$", "a"));
+        assert!(matches("^a{2,3}$", "aaa") && !matches("^a{2,3}$", "aaaa"));
+        assert!(matches("^(a*)*$", "aaa") && matches("^(a|b?)*c", "abbac"));
+        // This would take exponential time with backtracking.
+        let text = "a".repeat(30);
+        assert!(!matches("^(a|aa)*b$", &text));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let regex = Regex::new_ignore_case("^hello [a-z]+$").unwrap();
+        assert!(regex.is_match("HeLLo World"));
+        assert!(!Regex::new("^hello [a-z]+$").unwrap().is_match("HeLLo World"));
+        assert!(Regex::new_ignore_case("straße").unwrap().is_match("STRAßE"));
+        assert!(!Regex::new_ignore_case("[^a]").unwrap().is_match("A"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(error("ab(cd"), (RegexErrorKind::UnclosedGroup, 3));
+        assert_eq!(error("ab)cd"), (RegexErrorKind::UnmatchedParen, 3));
+        assert_eq!(error("x[abc"), (RegexErrorKind::UnclosedClass, 2));
+        assert_eq!(error("[z-a]"), (RegexErrorKind::InvalidRange, 2));
+        assert_eq!(error("a|*b"), (RegexErrorKind::MissingOperand, 3));
+        assert_eq!(error("a{2"), (RegexErrorKind::InvalidRepetition, 2));
+        assert_eq!(error("a{3,2}"), (RegexErrorKind::InvalidRepetition, 2));
+        assert_eq!(error("a{1001}"), (RegexErrorKind::TooLarge, 2));
+        assert_eq!(error("(a{1000}){1000}").0, RegexErrorKind::TooLarge);
+        assert_eq!(error("ab\\"), (RegexErrorKind::TrailingBackslash, 3));
+        assert_eq!(error("☃\\q"), (RegexErrorKind::UnknownEscape, 2));
+        assert_eq!(Regex::new("a(b").unwrap_err().to_string(), "unclosed group at column 2");
+    }
+
+    #[test]
+    fn test_escape() {
+        let text = r"1+1=2? (yes) [a-z] {x} ^$|\.*";
+        assert!(Regex::new(&escape(text)).unwrap().is_match(text));
+        assert!(!Regex::new(&escape("a.c")).unwrap().is_match("abc"));
+    }
+
+    #[test]
+    fn test_cache_limit() {
+        // The DFA states have to remember where the last 21 `a` were, so random text overflows the cache.
+        let regex = Regex::new("a.{20}$").unwrap();
+        // A xorshift generator, so that the test does not depend on anything outside of this file.
+        let mut state = 21u64;
+        let text: String = (0..MAX_DFA_STATES * 4).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if state & 1 == 0 { 'a' } else { 'b' }
+        }).collect();
+        let text = text + &"b".repeat(21);
+        assert!(!regex.is_match(&text));
+        let text = text + "a" + &"b".repeat(20);
+        assert!(regex.is_match(&text));
+        assert!(regex.clone().is_match(&text));
+    }
+}
@@ -19,6 +19,9 @@ mod part14;
 mod part15;
 mod part16;
 
+// The regular expression engine that rgrep can use (see part 13).
+mod regex;
+
 // This decides which part is actually run.
 fn main() {
    part00::main();
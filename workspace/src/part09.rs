@@ -7,6 +7,7 @@ use part05::BigInt;
 pub struct Iter<'a> {
     num: &'a BigInt,
     idx: usize, // the index of the last number that was returned
+    back_taken: usize, // the number of digits that were returned from the back (see below)
 }
 
 // Now we are equipped to implement `Iterator` for `Iter`.
@@ -16,7 +17,7 @@ impl<'a> Iterator for Iter<'a> {
 
     fn next(&mut self) -> Option<u64> {
         // First, check whether there's any more digits to return.
-        if self.idx == 0 {
+        if self.idx == self.back_taken {
             // We already returned all the digits, nothing to do.
             unimplemented!()
         } else {
@@ -24,6 +25,11 @@ impl<'a> Iterator for Iter<'a> {
             unimplemented!()
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.idx - self.back_taken;
+        (len, Some(len))
+    }
 }
 
 // All we need now is a function that creates such an iterator for a given `BigInt`.
@@ -62,6 +68,18 @@ fn print_digits_v2(b: &BigInt) {
 // **Exercise 09.2**: Write a function `iter_ldf` that iterators over the digits with the least-significant
 // digits coming first. Write a testcase for it.
 
+// ## Iterating from both ends
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<u64> {
+        if self.back_taken == self.idx {
+            unimplemented!()
+        } else {
+            unimplemented!()
+        }
+    }
+}
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
 // ## Iterator invalidation and lifetimes
 
 fn iter_invalidation_demo() {
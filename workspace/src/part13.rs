@@ -5,6 +5,7 @@ use std::io::prelude::*;
 use std::{io, fs, thread};
 use std::sync::mpsc::{sync_channel, SyncSender, Receiver};
 use std::sync::Arc;
+use regex::Regex;
 
 
 // Before we come to the actual code, we define a data-structure `Options` to store all the information we need
@@ -20,6 +21,7 @@ use self::OutputMode::*;
 pub struct Options {
     pub files: Vec<String>,
     pub pattern: String,
+    pub regex: Option<Regex>, // if this is `None`, we search for `pattern` literally
     pub output_mode: OutputMode,
 }
 
@@ -49,7 +51,12 @@ fn filter_lines(options: Arc<Options>,
     for line in in_channel.iter() {
         // `contains` works on lots of types of patterns, but in particular, we can use it to test whether
         // one string is contained in another. This is another example of Rust using traits as substitute for overloading.
-        if line.contains(&options.pattern) {
+        // If we were given a regular expression, we use the engine in `regex.rs` instead.
+        let is_match = match options.regex {
+            Some(ref regex) => regex.is_match(&line),
+            None => line.contains(&options.pattern),
+        };
+        if is_match {
             unimplemented!()
         }
     }
@@ -115,6 +122,7 @@ pub fn main() {
                     "src/part11.rs".to_string(),
                     "src/part12.rs".to_string()],
         pattern: "let".to_string(),
+        regex: None,
         output_mode: Print
     };
     run(options);
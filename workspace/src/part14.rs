@@ -90,6 +90,7 @@ Options:
         Options {
             files: files.iter().map(|file| file.to_string()).collect(),
             pattern: pattern.to_string(),
+            regex: None,
             output_mode: mode,
         }
     }
@@ -101,8 +102,7 @@ Options:
     }
 }
 
-// **Exercise 14.3**: Wouldn't it be nice if rgrep supported regular expressions? There's already a crate that does all the parsing and matching on regular
-// expression, it's called [regex](https://crates.io/crates/regex). Add this crate to the dependencies of your workspace, add an option ("-r") to switch
-// the pattern to regular-expression mode, and change `filter_lines` to honor this option. The documentation of regex is available from its crates.io site.
-// (You won't be able to use the `regex!` macro if you are on the stable or beta channel of Rust. But it wouldn't help for our use-case anyway.)
+// **Exercise 14.3**: Wouldn't it be nice if rgrep supported regular expressions? `filter_lines` already honors `options.regex`, using the
+// engine in `regex.rs`. Add an option ("-e") to switch the pattern to regular-expression mode, and compile the pattern with `Regex::new`.
+// If the pattern is invalid, print the error (it tells the column of the mistake) and exit.
 